@@ -0,0 +1,413 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use std::io::{self, Stderr, Write};
+
+use crate::index::Index;
+use crate::models::{Author, Field, Work, Works};
+
+/// フォーカスのあるペイン
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Pane {
+    Authors,
+    Titles,
+    Search,
+}
+
+/// 全画面のブラウザ (作者、作品名、書き出しのプレビュー)
+struct App<'a> {
+    works: &'a [Work],
+    index: Index,
+    /// 検索語に一致する作品
+    matched: Vec<Work>,
+    authors: Vec<Author>,
+    /// 選択中の作者の作品 (作品名順、同名の作品は作品ID順)
+    titles: Vec<Work>,
+    author_state: ListState,
+    title_state: ListState,
+    search: String,
+    pane: Pane,
+    status: String,
+    quit: bool,
+    /// 終了時に出力する作品
+    chosen: Option<Work>,
+}
+
+/// 作品をブラウズし、Enterで選択した作品を返す
+///
+/// - `/`: 検索 (作者、作品名、書き出しの全文検索)
+/// - `Tab`/`←`/`→`: ペイン切り替え、`↑`/`↓`/`j`/`k`: 移動
+/// - `r`: ランダムに作品を選ぶ
+/// - `c`: 書き出しをクリップボードにコピー (OSC 52)
+/// - `o`: 作品ページをブラウザで開く
+/// - `Enter`: 選択した作品を出力して終了、`q`/`Esc`: 終了
+///
+/// 標準出力をパイプで渡せるように、画面は標準エラー出力に描画する
+pub fn browse(works: &[Work]) -> io::Result<Option<Work>> {
+    let _guard = TerminalGuard::new()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+    let mut app = App::new(works);
+    app.run(&mut terminal)?;
+    Ok(app.chosen)
+}
+
+/// 端末をrawモードと代替画面に切り替え、破棄時 (エラーやパニックを含む) に元に戻す
+pub(crate) struct TerminalGuard;
+
+impl TerminalGuard {
+    pub(crate) fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        // 代替画面への切り替えに失敗してもrawモードを解除する
+        let guard = TerminalGuard;
+        execute!(io::stderr(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl<'a> App<'a> {
+    fn new(works: &'a [Work]) -> Self {
+        let mut app = App {
+            works,
+            index: Index::new(works),
+            matched: works.to_vec(),
+            authors: Vec::new(),
+            titles: Vec::new(),
+            author_state: ListState::default(),
+            title_state: ListState::default(),
+            search: String::new(),
+            pane: Pane::Authors,
+            status: String::new(),
+            quit: false,
+            chosen: None,
+        };
+        app.update_authors();
+        app
+    }
+
+    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<Stderr>>) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.handle_key(key);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        if self.pane == Pane::Search {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Down | KeyCode::Tab => {
+                    self.pane = Pane::Authors;
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.update_search();
+                }
+                KeyCode::Char(c) => {
+                    self.search.push(c);
+                    self.update_search();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        self.status.clear();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('/') => self.pane = Pane::Search,
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right | KeyCode::Char('h' | 'l') => {
+                self.pane = match self.pane {
+                    Pane::Authors => Pane::Titles,
+                    _ => Pane::Authors,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Char('r') => self.shuffle(),
+            KeyCode::Char('c') => self.copy(),
+            KeyCode::Char('o') => self.open(),
+            KeyCode::Enter => {
+                self.chosen = self.selected().cloned();
+                self.quit = self.chosen.is_some();
+            }
+            _ => {}
+        }
+    }
+
+    /// 検索語で作品を絞り込む (空の場合は全作品)
+    fn update_search(&mut self) {
+        let terms: Vec<String> = self.search.split_whitespace().map(String::from).collect();
+        self.matched = if terms.is_empty() {
+            self.works.to_vec()
+        } else {
+            self.index
+                .search(self.works, &terms, self.works.len())
+                .into_iter()
+                .map(|hit| self.works[hit.index].clone())
+                .collect()
+        };
+        self.update_authors();
+    }
+
+    fn update_authors(&mut self) {
+        self.authors = self.matched.authors();
+        self.author_state
+            .select((!self.authors.is_empty()).then_some(0));
+        self.update_titles();
+    }
+
+    fn update_titles(&mut self) {
+        self.titles = self
+            .author_state
+            .selected()
+            .map(|i| {
+                let author_id = &self.authors[i].id;
+                let mut works: Vec<Work> = self
+                    .matched
+                    .iter()
+                    .filter(|work| work.author_id == *author_id)
+                    .cloned()
+                    .collect();
+                works.sort_by(|a, b| a.title.cmp(&b.title).then_with(|| a.id.cmp(&b.id)));
+                works
+            })
+            .unwrap_or_default();
+        self.title_state
+            .select((!self.titles.is_empty()).then_some(0));
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (state, len) = match self.pane {
+            Pane::Authors => (&mut self.author_state, self.authors.len()),
+            _ => (&mut self.title_state, self.titles.len()),
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0);
+        state.select(Some(current.saturating_add_signed(delta).min(len - 1)));
+        if self.pane == Pane::Authors {
+            self.update_titles();
+        }
+    }
+
+    /// 一致する作品からランダムに1件選ぶ
+    fn shuffle(&mut self) {
+        let Some(work) = self.matched.random(Some(rand::random())).first().cloned() else {
+            return;
+        };
+        self.author_state.select(
+            self.authors
+                .iter()
+                .position(|author| author.id == work.author_id),
+        );
+        self.update_titles();
+        self.title_state
+            .select(self.titles.iter().position(|title| title.id == work.id));
+    }
+
+    fn selected(&self) -> Option<&Work> {
+        self.titles.get(self.title_state.selected()?)
+    }
+
+    /// OSC 52で書き出しを端末のクリップボードにコピーする
+    fn copy(&mut self) {
+        let Some(text) = self.selected().map(|work| work.text.clone()) else {
+            return;
+        };
+        let mut stderr = io::stderr();
+        let result = write!(stderr, "\x1b]52;c;{}\x07", STANDARD.encode(&text))
+            .and_then(|()| stderr.flush());
+        self.status = match result {
+            Ok(()) => "Copied to clipboard".to_string(),
+            Err(e) => format!("Failed to copy: {e}"),
+        };
+    }
+
+    fn open(&mut self) {
+        let Some(url) = self
+            .selected()
+            .map(|work| Field::Url.value(work).to_string())
+        else {
+            return;
+        };
+        self.status = if url.is_empty() {
+            "No URL for this work".to_string()
+        } else {
+            match open::that_detached(&url) {
+                Ok(()) => format!("Opened {url}"),
+                Err(e) => format!("Failed to open {url}: {e}"),
+            }
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let [search, body, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [authors, titles, opening] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(30),
+            Constraint::Percentage(45),
+        ])
+        .areas(body);
+
+        frame.render_widget(
+            Paragraph::new(self.search.as_str()).block(self.block(
+                &format!("Search ({} works)", self.matched.len()),
+                Pane::Search,
+            )),
+            search,
+        );
+
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+        let author_list = List::new(self.authors.iter().map(|author| author.name.as_str()))
+            .block(self.block("Authors", Pane::Authors))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(author_list, authors, &mut self.author_state);
+        // 同名の作品は作品IDを添えて区別する
+        let labels = self.titles.iter().enumerate().map(|(i, work)| {
+            let duplicate = [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|j| self.titles.get(j))
+                .any(|other| other.title == work.title);
+            if duplicate {
+                format!("{} ({})", work.title, work.id)
+            } else {
+                work.title.clone()
+            }
+        });
+        let title_list = List::new(labels)
+            .block(self.block("Titles", Pane::Titles))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(title_list, titles, &mut self.title_state);
+
+        frame.render_widget(
+            Paragraph::new(self.selected().map_or_else(Vec::new, preview))
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Opening")),
+            opening,
+        );
+
+        let help_text = if self.status.is_empty() {
+            "/ search  tab switch  ↑↓ move  r shuffle  c copy  o open  enter print  q quit"
+        } else {
+            self.status.as_str()
+        };
+        frame.render_widget(
+            Line::styled(help_text, Style::default().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    fn block(&self, title: &str, pane: Pane) -> Block<'static> {
+        let block = Block::bordered().title(title.to_string());
+        if self.pane == pane {
+            block.border_style(Style::default().fg(Color::Green))
+        } else {
+            block
+        }
+    }
+}
+
+/// 書き出しのプレビュー (書き出し全体、作者『作品名』、URL)
+pub(crate) fn preview(work: &Work) -> Vec<Line<'_>> {
+    vec![
+        Line::from(work.text.as_str()),
+        Line::default(),
+        Line::from(vec![
+            Span::styled(work.author.as_str(), Style::default().fg(Color::Cyan)),
+            Span::raw("『"),
+            Span::styled(work.title.as_str(), Style::default().fg(Color::Yellow)),
+            Span::raw("』"),
+        ]),
+        Line::styled(Field::Url.value(work), Style::default().fg(Color::DarkGray)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{sample_work, sample_works};
+
+    fn press(app: &mut App<'_>, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn names(app: &App<'_>) -> Vec<String> {
+        app.authors
+            .iter()
+            .map(|author| author.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_navigation_and_search() {
+        let works = sample_works();
+        let mut app = App::new(&works);
+        assert_eq!(names(&app), vec!["夏目 漱石", "宮沢 賢治"]);
+        assert_eq!(app.selected().map(|work| work.id.as_str()), Some("789"));
+
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected().map(|work| work.id.as_str()), Some("752"));
+
+        press(&mut app, KeyCode::Char('/'));
+        for c in "よだか".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(names(&app), vec!["宮沢 賢治"]);
+        assert_eq!(app.selected().map(|work| work.id.as_str()), Some("473"));
+
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.selected().map(|work| work.id.as_str()), Some("473"));
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.quit);
+        assert_eq!(app.chosen.map(|work| work.id), Some("473".to_string()));
+    }
+
+    #[test]
+    fn test_same_title() {
+        // 同じ作者の同名の作品は作品IDで区別する
+        let works = vec![
+            sample_work("2", "宮沢 賢治", "81", "春", "二。"),
+            sample_work("1", "宮沢 賢治", "81", "春", "一。"),
+        ];
+        let mut app = App::new(&works);
+        assert_eq!(app.selected().map(|work| work.id.as_str()), Some("1"));
+
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected().map(|work| work.text.as_str()), Some("二。"));
+    }
+}
@@ -0,0 +1,584 @@
+use serde::Serialize;
+use unicode_width::UnicodeWidthChar;
+
+use crate::models::{Field, Work};
+
+/// 縦書きの列の高さのデフォルト値
+pub const TATEGAKI_HEIGHT: usize = 20;
+
+/// ルビ付きテキストの構成要素
+#[derive(Debug, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Ruby { base: &'a str, ruby: &'a str },
+}
+
+/// `｜親文字《ルビ》`形式のテキストを分割する
+pub fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('｜') {
+        let Some((base, ruby, after)) =
+            rest[start + '｜'.len_utf8()..]
+                .split_once('《')
+                .and_then(|(base, after)| {
+                    after
+                        .split_once('》')
+                        .map(|(ruby, after)| (base, ruby, after))
+                })
+        else {
+            break;
+        };
+
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Ruby { base, ruby });
+        rest = after;
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+/// 本文の構成要素 (`ruby`指定時かつルビ付きテキストがあればルビを含む)
+fn body(work: &Work, ruby: bool) -> Vec<Segment<'_>> {
+    match Field::Ruby.value(work) {
+        text if ruby && !text.is_empty() => segments(text),
+        _ => vec![Segment::Text(&work.text)],
+    }
+}
+
+/// Markdown形式 (引用 + 出典リンク)
+pub fn markdown(work: &Work) -> String {
+    let title = escape_markdown(&work.title);
+    let title = match Field::Url.value(work) {
+        "" => title,
+        url => format!("[{title}](<{url}>)"),
+    };
+    format!(
+        "> {}\n>\n> — {}『{title}』",
+        escape_markdown(&work.text),
+        escape_markdown(&work.author)
+    )
+}
+
+/// HTML形式 (`<blockquote cite>` + 任意でルビ)
+pub fn html(work: &Work, ruby: bool) -> String {
+    let text: String = body(work, ruby)
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => escape_html(text),
+            Segment::Ruby { base, ruby } => format!(
+                "<ruby>{}<rp>（</rp><rt>{}</rt><rp>）</rp></ruby>",
+                escape_html(base),
+                escape_html(ruby)
+            ),
+        })
+        .collect();
+
+    let title = escape_html(&work.title);
+    let (cite, title) = match Field::Url.value(work) {
+        "" => (String::new(), title),
+        url => {
+            let url = escape_html(url);
+            (
+                format!(" cite=\"{url}\""),
+                format!("<a href=\"{url}\">{title}</a>"),
+            )
+        }
+    };
+
+    format!(
+        "<blockquote{cite}>\n  <p>{text}</p>\n  <footer>— {}『<cite>{title}</cite>』</footer>\n</blockquote>",
+        escape_html(&work.author)
+    )
+}
+
+/// `LaTeX`形式 (`quote`環境 + 任意で`\ruby`)
+///
+/// リンクには`hyperref`、ルビには`\ruby`を提供するパッケージ (`pxrubrica`等) が必要
+pub fn latex(work: &Work, ruby: bool) -> String {
+    let text: String = body(work, ruby)
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => escape_latex(text),
+            Segment::Ruby { base, ruby } => {
+                format!("\\ruby{{{}}}{{{}}}", escape_latex(base), escape_latex(ruby))
+            }
+        })
+        .collect();
+
+    let title = escape_latex(&work.title);
+    let title = match Field::Url.value(work) {
+        "" => title,
+        url => format!(
+            "\\href{{{}}}{{{title}}}",
+            url.replace('%', "\\%").replace('#', "\\#")
+        ),
+    };
+
+    format!(
+        "\\begin{{quote}}\n{text}\n\\par\\hfill ---{}『{title}』\n\\end{{quote}}",
+        escape_latex(&work.author)
+    )
+}
+
+/// fortune(6)形式 (エントリを`%`行で区切る)
+///
+/// `cargo xtask export-fortune`で書き出すエントリと同じ形式
+pub fn fortune(work: &Work) -> String {
+    format!("{}\n\t\t-- {}『{}』\n%", work.text, work.author, work.title)
+}
+
+/// 出典 (`{citation}`プレースホルダ)
+///
+/// 作者『作品名』青空文庫に続けて底本・初出・入力・校正と図書カードのURLを並べる
+pub fn citation(work: &Work) -> String {
+    let mut parts = vec![format!("{}『{}』青空文庫", work.author, work.title)];
+    parts.extend(credits(work));
+    match link(work) {
+        "" => {}
+        url => parts.push(url.to_string()),
+    }
+    parts.join("　")
+}
+
+/// `BibTeX`形式 (biblatexの`@online`)
+///
+/// 底本の書名、出版社、発行年を`origtitle`/`origpublisher`/`origdate`とし、
+/// 底本・初出・入力・校正は`note`に含める
+pub fn bibtex(work: &Work) -> String {
+    let base = BaseText::parse(Field::BaseText.value(work));
+    let mut fields = vec![
+        ("author", bibtex_name(&work.author)),
+        ("title", escape_latex(&work.title)),
+        ("organization", "青空文庫".to_string()),
+    ];
+    if let Some(title) = base.title {
+        fields.push(("origtitle", escape_latex(title)));
+    }
+    if let Some(publisher) = base.publisher {
+        fields.push(("origpublisher", escape_latex(publisher)));
+    }
+    if let Some(year) = base.year {
+        fields.push(("origdate", year.to_string()));
+    }
+    match link(work) {
+        "" => {}
+        url => fields.push(("url", url.to_string())),
+    }
+    let credits = credits(work);
+    if !credits.is_empty() {
+        fields.push(("note", escape_latex(&credits.join("　"))));
+    }
+
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("  {key} = {{{value}}},"))
+        .collect();
+    format!(
+        "@online{{{},\n{}\n}}",
+        citation_key(work),
+        fields.join("\n")
+    )
+}
+
+/// CSL-JSONの項目 (青空文庫のページ、底本を`original-*`とする)
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct CslItem<'a> {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'a str,
+    author: [CslName<'a>; 1],
+    container_title: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_publisher: Option<&'a str>,
+    /// 底本の発行年
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_date: Option<CslDate>,
+    #[serde(rename = "URL", skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    /// 底本・初出・入力・校正 (1行1項目)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+/// CSL-JSONの日付 (年のみ)
+#[derive(Serialize, Debug)]
+struct CslDate {
+    #[serde(rename = "date-parts")]
+    date_parts: [[u16; 1]; 1],
+}
+
+/// CSL-JSONの人名 (姓と名の間に空白がない場合は`literal`)
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+enum CslName<'a> {
+    Structured { family: &'a str, given: &'a str },
+    Literal { literal: &'a str },
+}
+
+/// CSL-JSON形式の項目
+pub fn csl(work: &Work) -> CslItem<'_> {
+    let author = match work.author.split_once(' ') {
+        Some((family, given)) => CslName::Structured { family, given },
+        None => CslName::Literal {
+            literal: &work.author,
+        },
+    };
+    let base = BaseText::parse(Field::BaseText.value(work));
+    let credits = credits(work);
+    CslItem {
+        id: citation_key(work),
+        kind: "webpage",
+        title: &work.title,
+        author: [author],
+        container_title: "青空文庫",
+        original_title: base.title,
+        original_publisher: base.publisher,
+        original_date: base.year.map(|year| CslDate {
+            date_parts: [[year]],
+        }),
+        url: Some(link(work)).filter(|url| !url.is_empty()),
+        note: (!credits.is_empty()).then(|| credits.join("\n")),
+    }
+}
+
+/// 底本の書名、出版社、発行年
+///
+/// `「書名」叢書名、出版社 1987（昭和62）年10月27日第1刷発行`の形式を想定し、
+/// 読み取れない項目はNone
+#[derive(Debug, Default, PartialEq)]
+struct BaseText<'a> {
+    title: Option<&'a str>,
+    publisher: Option<&'a str>,
+    year: Option<u16>,
+}
+
+impl<'a> BaseText<'a> {
+    fn parse(text: &'a str) -> Self {
+        let Some((title, rest)) = text
+            .strip_prefix('「')
+            .and_then(|rest| rest.split_once('」'))
+        else {
+            return BaseText {
+                year: year(text),
+                ..BaseText::default()
+            };
+        };
+        // 発行年月日の前までの最後の`、`以降 (叢書名を除く)
+        let publisher = rest
+            .split(|c: char| c.is_whitespace() || is_digit(c))
+            .next()
+            .and_then(|publishers| publishers.rsplit('、').next())
+            .map(str::trim)
+            .filter(|publisher| !publisher.is_empty());
+        BaseText {
+            title: Some(title).filter(|title| !title.is_empty()),
+            publisher,
+            year: year(rest),
+        }
+    }
+}
+
+/// 最初の`NNNN（`または`NNNN年`の4桁の西暦 (全角数字を含む)
+fn year(text: &str) -> Option<u16> {
+    let chars: Vec<char> = text.chars().collect();
+    (0..chars.len().saturating_sub(4)).find_map(|i| {
+        let digits = &chars[i..i + 4];
+        let starts = i == 0 || !is_digit(chars[i - 1]);
+        if !starts
+            || !digits.iter().all(|c| is_digit(*c))
+            || !matches!(chars[i + 4], '（' | '(' | '年')
+        {
+            return None;
+        }
+        let year = digits.iter().fold(0, |year, c| year * 10 + digit(*c));
+        u16::try_from(year).ok()
+    })
+}
+
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit() || ('０'..='９').contains(&c)
+}
+
+/// 数字の値 (全角数字を含む)
+fn digit(c: char) -> u32 {
+    c.to_digit(10).unwrap_or_else(|| c as u32 - '０' as u32)
+}
+
+/// 底本・初出・入力・校正 (データセットにある項目のみ)
+fn credits(work: &Work) -> Vec<String> {
+    [
+        ("底本", Field::BaseText),
+        ("初出", Field::FirstPublished),
+        ("入力", Field::Inputter),
+        ("校正", Field::Proofreader),
+    ]
+    .iter()
+    .filter_map(|(label, field)| match field.value(work) {
+        "" => None,
+        value => Some(format!("{label}：{value}")),
+    })
+    .collect()
+}
+
+/// 出典のURL (図書カード、なければ作品ファイル)
+fn link(work: &Work) -> &str {
+    match Field::CardUrl.value(work) {
+        "" => Field::Url.value(work),
+        url => url,
+    }
+}
+
+/// 引用キー (共著の作品を区別するため人物IDを含める)
+fn citation_key(work: &Work) -> String {
+    format!("aozora-{}-{}", work.id, work.author_id)
+}
+
+/// `BibTeX`の人名 (`姓, 名`、空白がない場合は波括弧で囲んで1語とする)
+fn bibtex_name(author: &str) -> String {
+    match author.split_once(' ') {
+        Some((family, given)) => format!("{}, {}", escape_latex(family), escape_latex(given)),
+        None => format!("{{{}}}", escape_latex(author)),
+    }
+}
+
+/// 縦書き形式 (右から左へ列を並べ、最終列に作者と作品名)
+///
+/// 1マスは全角1文字分 (表示幅2) とし、半角文字は全角に変換するか空白で補う
+pub fn tategaki(work: &Work, height: usize) -> String {
+    let height = height.max(1);
+    let text: Vec<char> = work.text.chars().filter_map(vertical_char).collect();
+    let attribution: Vec<char> = format!("{}　{}", work.author, work.title)
+        .chars()
+        .filter_map(vertical_char)
+        .collect();
+
+    // 本文の最長の列か作者と作品名の長さに高さを揃える
+    let height = text.len().max(attribution.len()).min(height);
+
+    // 右から順に本文、空列、作者と作品名 (作者と作品名は下揃え)
+    let padding = attribution.len().div_ceil(height) * height - attribution.len();
+    let attribution: Vec<char> = std::iter::repeat_n('　', padding)
+        .chain(attribution)
+        .collect();
+    let columns: Vec<&[char]> = text
+        .chunks(height)
+        .chain(std::iter::once(&[][..]))
+        .chain(attribution.chunks(height))
+        .collect();
+
+    (0..height)
+        .map(|row| {
+            columns
+                .iter()
+                .rev()
+                .map(|column| cell(column.get(row).copied().unwrap_or('　')))
+                .collect::<Vec<String>>()
+                .join(" ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// 縦書き用の文字に変換 (表示幅0の文字は除く)
+fn vertical_char(c: char) -> Option<char> {
+    let c = match c {
+        'ー' | '―' | '－' | '-' => '｜',
+        '—' => '︱',
+        '…' => '︙',
+        '‥' => '︰',
+        '、' => '︑',
+        '。' => '︒',
+        '「' => '﹁',
+        '」' => '﹂',
+        '『' => '﹃',
+        '』' => '﹄',
+        '（' | '(' => '︵',
+        '）' | ')' => '︶',
+        '〔' => '︹',
+        '〕' => '︺',
+        '【' => '︻',
+        '】' => '︼',
+        '《' => '︽',
+        '》' => '︾',
+        '〈' => '︿',
+        '〉' => '﹀',
+        '［' | '[' => '﹇',
+        '］' | ']' => '﹈',
+        '｛' | '{' => '︷',
+        '｝' | '}' => '︸',
+        '～' | '〜' => '≀',
+        ' ' => '　',
+        // 半角英数記号は全角に変換
+        '!'..='~' => char::from_u32(c as u32 - 0x21 + 0xFF01).unwrap_or(c),
+        c => c,
+    };
+    (c.width().unwrap_or(0) > 0).then_some(c)
+}
+
+/// 1マス (表示幅2) に揃える
+fn cell(c: char) -> String {
+    if c.width() == Some(1) {
+        format!("{c} ")
+    } else {
+        c.to_string()
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.chars().fold(String::new(), |mut output, c| {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            output.push('\\');
+        }
+        output.push(c);
+        output
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn escape_latex(text: &str) -> String {
+    text.chars().fold(String::new(), |mut output, c| {
+        match c {
+            '\\' => output.push_str("\\textbackslash{}"),
+            '~' => output.push_str("\\textasciitilde{}"),
+            '^' => output.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                output.push('\\');
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(
+            segments("｜親譲《おやゆず》りの｜無鉄砲《むてっぽう》で"),
+            vec![
+                Segment::Ruby {
+                    base: "親譲",
+                    ruby: "おやゆず"
+                },
+                Segment::Text("りの"),
+                Segment::Ruby {
+                    base: "無鉄砲",
+                    ruby: "むてっぽう"
+                },
+                Segment::Text("で"),
+            ]
+        );
+        assert_eq!(segments("ルビなし｜"), vec![Segment::Text("ルビなし｜")]);
+    }
+
+    #[test]
+    fn test_tategaki() {
+        let work: Work = serde_json::from_str(
+            r#"{"id":"1","author":"著者","author_id":"1","title":"題","text":"「あー」、ABｶ。","url":null,"ruby":null}"#,
+        )
+        .expect("Failed to parse work");
+        assert_eq!(
+            tategaki(&work, 4),
+            [
+                "著 　 ︒ ︑ ﹁",
+                "者 　 　 Ａ あ",
+                "　 　 　 Ｂ ｜",
+                "題 　 　 ｶ  ﹂",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_citation() {
+        let work: Work = serde_json::from_str(
+            r#"{"id":"752","author":"夏目 漱石","author_id":"148","title":"坊っちゃん","text":"親譲りの無鉄砲で小供の時から損ばかりして居る。","url":null,"ruby":null,"card_url":"https://www.aozora.gr.jp/cards/000148/card752.html","base_text":"「夏目漱石全集２」ちくま文庫、筑摩書房 1987（昭和62）年10月27日第1刷発行","inputter":"Ｊｕｎ","proofreader":"伊藤時也"}"#,
+        )
+        .expect("Failed to parse work");
+
+        assert_eq!(
+            citation(&work),
+            "夏目 漱石『坊っちゃん』青空文庫　底本：「夏目漱石全集２」ちくま文庫、筑摩書房 1987（昭和62）年10月27日第1刷発行　入力：Ｊｕｎ　校正：伊藤時也　https://www.aozora.gr.jp/cards/000148/card752.html"
+        );
+        assert_eq!(
+            bibtex(&work),
+            [
+                "@online{aozora-752-148,",
+                "  author = {夏目, 漱石},",
+                "  title = {坊っちゃん},",
+                "  organization = {青空文庫},",
+                "  origtitle = {夏目漱石全集２},",
+                "  origpublisher = {筑摩書房},",
+                "  origdate = {1987},",
+                "  url = {https://www.aozora.gr.jp/cards/000148/card752.html},",
+                "  note = {底本：「夏目漱石全集２」ちくま文庫、筑摩書房 1987（昭和62）年10月27日第1刷発行　入力：Ｊｕｎ　校正：伊藤時也},",
+                "}",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            serde_json::to_string(&csl(&work)).expect("Failed to serialize to JSON"),
+            r#"{"id":"aozora-752-148","type":"webpage","title":"坊っちゃん","author":[{"family":"夏目","given":"漱石"}],"container-title":"青空文庫","original-title":"夏目漱石全集２","original-publisher":"筑摩書房","original-date":{"date-parts":[[1987]]},"URL":"https://www.aozora.gr.jp/cards/000148/card752.html","note":"底本：「夏目漱石全集２」ちくま文庫、筑摩書房 1987（昭和62）年10月27日第1刷発行\n入力：Ｊｕｎ\n校正：伊藤時也"}"#
+        );
+    }
+
+    #[test]
+    fn test_base_text() {
+        assert_eq!(
+            BaseText::parse(
+                "「宮沢賢治全集５」ちくま文庫、筑摩書房　１９８６（昭和６１）年１２月１日第１刷発行"
+            ),
+            BaseText {
+                title: Some("宮沢賢治全集５"),
+                publisher: Some("筑摩書房"),
+                year: Some(1986),
+            }
+        );
+        assert_eq!(
+            BaseText::parse("「坊っちゃん」新潮文庫、新潮社 1950（昭和25）年1月31日発行"),
+            BaseText {
+                title: Some("坊っちゃん"),
+                publisher: Some("新潮社"),
+                year: Some(1950),
+            }
+        );
+        assert_eq!(BaseText::parse(""), BaseText::default());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!(escape_latex("100% & $_#{}"), "100\\% \\& \\$\\_\\#\\{\\}");
+        assert_eq!(escape_markdown("*強調*"), "\\*強調\\*");
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{Query, QueryKey, Work};
+
+/// 検索対象の項目と順位付けの重み
+const WEIGHTS: [(QueryKey, usize); 3] = [
+    (QueryKey::Title, 3),
+    (QueryKey::Author, 2),
+    (QueryKey::Text, 1),
+];
+
+/// 作者・作品名・書き出しのn-gram (1-gram + 2-gram) と人物IDの転置インデックス
+///
+/// n-gramの積集合で候補を絞り込んだ後、`contains`で照合する
+pub struct Index {
+    postings: HashMap<(QueryKey, String), Vec<usize>>,
+}
+
+/// 検索結果
+#[derive(Debug, PartialEq)]
+pub struct Hit {
+    /// 作品の添字
+    pub index: usize,
+    pub score: usize,
+}
+
+impl Index {
+    pub fn new(works: &[Work]) -> Self {
+        let mut postings: HashMap<(QueryKey, String), Vec<usize>> = HashMap::new();
+        for (index, work) in works.iter().enumerate() {
+            for (key, _) in WEIGHTS {
+                let grams: BTreeSet<String> = grams(key.value(work)).into_iter().collect();
+                for gram in grams {
+                    postings.entry((key, gram)).or_default().push(index);
+                }
+            }
+            postings
+                .entry((QueryKey::AuthorId, work.author_id.clone()))
+                .or_default()
+                .push(index);
+        }
+        Index { postings }
+    }
+
+    /// `Works::filter`と同じ結果をインデックスを使って返す
+    pub fn filter(&self, works: &[Work], queries: &[Query]) -> Vec<Work> {
+        let candidates = queries
+            .iter()
+            .filter_map(|query| self.candidates(query.key, &query.value))
+            .reduce(|a, b| intersect(&a, &b));
+
+        match candidates {
+            Some(candidates) => candidates
+                .into_iter()
+                .map(|index| &works[index])
+                .filter(|work| queries.iter().all(|query| query.matches(work)))
+                .cloned()
+                .collect(),
+            None => works
+                .iter()
+                .filter(|work| queries.iter().all(|query| query.matches(work)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// 全ての語を (いずれかの項目に) 含む作品を出現回数と項目の重みで順位付けして返す
+    pub fn search(&self, works: &[Work], terms: &[String], limit: usize) -> Vec<Hit> {
+        let terms: Vec<&String> = terms.iter().filter(|term| !term.is_empty()).collect();
+        let Some(candidates) = terms
+            .iter()
+            .map(|term| {
+                WEIGHTS
+                    .iter()
+                    .filter_map(|(key, _)| self.candidates(*key, term))
+                    .fold(Vec::new(), |a, b| union(&a, &b))
+            })
+            .reduce(|a, b| intersect(&a, &b))
+        else {
+            return Vec::new();
+        };
+
+        let mut hits: Vec<Hit> = candidates
+            .into_iter()
+            .filter_map(|index| {
+                let work = &works[index];
+                terms
+                    .iter()
+                    .map(|term| {
+                        WEIGHTS
+                            .iter()
+                            .map(|(key, weight)| {
+                                key.value(work).matches(term.as_str()).count() * weight
+                            })
+                            .sum::<usize>()
+                    })
+                    .try_fold(0, |score, term_score| {
+                        (term_score > 0).then_some(score + term_score)
+                    })
+                    .map(|score| Hit { index, score })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// 語の全n-gramを含む作品の添字 (昇順)
+    ///
+    /// 空の語は絞り込まないためNoneを返す (人物IDは完全一致)
+    fn candidates(&self, key: QueryKey, term: &str) -> Option<Vec<usize>> {
+        if key == QueryKey::AuthorId {
+            return Some(
+                self.postings
+                    .get(&(key, term.to_string()))
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+
+        let chars: Vec<char> = term.chars().collect();
+        let grams: Vec<String> = match chars.len() {
+            0 => return None,
+            1 => vec![term.to_string()],
+            _ => chars.windows(2).map(|w| w.iter().collect()).collect(),
+        };
+
+        grams
+            .iter()
+            .map(|gram| {
+                self.postings
+                    .get(&(key, gram.clone()))
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .reduce(|a, b| intersect(&a, &b))
+    }
+}
+
+/// 強調表示 (ANSIエスケープで反転表示、着色しない場合は`【】`で囲む)
+pub fn highlight(text: &str, terms: &[String], color: bool) -> String {
+    let (open, close) = if color {
+        ("\x1b[1;7m", "\x1b[0m")
+    } else {
+        ("【", "】")
+    };
+
+    // 重なりを除いた一致範囲
+    let mut ranges: Vec<(usize, usize)> = terms
+        .iter()
+        .filter(|term| !term.is_empty())
+        .flat_map(|term| {
+            text.match_indices(term.as_str())
+                .map(|(start, matched)| (start, start + matched.len()))
+        })
+        .collect();
+    ranges.sort_unstable();
+
+    let mut output = String::new();
+    let mut position = 0;
+    for (start, end) in ranges {
+        if start < position {
+            continue;
+        }
+        output.push_str(&text[position..start]);
+        output.push_str(open);
+        output.push_str(&text[start..end]);
+        output.push_str(close);
+        position = end;
+    }
+    output.push_str(&text[position..]);
+    output
+}
+
+fn grams(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .iter()
+        .map(char::to_string)
+        .chain(chars.windows(2).map(|w| w.iter().collect()))
+        .collect()
+}
+
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let (mut i, mut j) = (0, 0);
+    let mut output = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                output.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    output
+}
+
+fn union(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut output: Vec<usize> = a.iter().chain(b).copied().collect();
+    output.sort_unstable();
+    output.dedup();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Works, sample_work, sample_works};
+
+    fn works() -> Vec<Work> {
+        let mut works = sample_works()[..2].to_vec();
+        works.push(sample_work(
+            "1",
+            "作者",
+            "1",
+            "猫町",
+            "猫の猫による猫のための町。",
+        ));
+        works
+    }
+
+    #[test]
+    fn test_filter_same_as_linear_scan() {
+        let works = works();
+        let index = Index::new(&works);
+        for query in [
+            "author=夏目",
+            "text=猫である",
+            "title=猫",
+            "text=犬",
+            "text=",
+            "author=漱石 ",
+            "author_id=148",
+            "author_id=14",
+        ] {
+            let queries = vec![query.parse::<Query>().expect("Invalid query")];
+            let ids = |works: Vec<Work>| works.into_iter().map(|w| w.id).collect::<Vec<_>>();
+            assert_eq!(
+                ids(index.filter(&works, &queries)),
+                ids(works.filter(&queries))
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_ranking() {
+        let works = works();
+        let index = Index::new(&works);
+        let hits = index.search(&works, &["猫".to_string()], 10);
+        // 猫町: 作品名 3 + 書き出し 3, 吾輩は猫である: 作品名 3 + 書き出し 1
+        assert_eq!(
+            hits,
+            vec![Hit { index: 2, score: 6 }, Hit { index: 1, score: 4 }]
+        );
+        assert!(
+            index
+                .search(&works, &["猫".to_string(), "夏目".to_string()], 10)
+                .len()
+                == 1
+        );
+        assert!(index.search(&works, &["猫犬".to_string()], 10).is_empty());
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            highlight(
+                "吾輩は猫である。",
+                &["猫".to_string(), "ある".to_string()],
+                false
+            ),
+            "吾輩は【猫】で【ある】。"
+        );
+    }
+}
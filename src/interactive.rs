@@ -0,0 +1,461 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, IsTerminal, Stderr};
+
+use crate::browse::{self, TerminalGuard};
+use crate::models::{Field, Work, Works};
+use crate::romaji::to_romaji;
+
+/// 対話モードを終了した理由
+#[derive(Debug)]
+pub enum Error {
+    /// Escでキャンセルした
+    Canceled,
+    /// Ctrl-Cで中断した
+    Interrupted,
+    /// 標準エラー出力または入力が端末でない
+    NotTty,
+    Io(io::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// 選択肢
+enum Choice<'a> {
+    /// 作品 (`作者 / 作品名`)
+    Work(&'a Work),
+    /// 作者の作品からランダムに1件 (`作者 / (any title)`)
+    AnyTitle(&'a Work),
+}
+
+impl fmt::Display for Choice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Choice::Work(work) => write!(f, "{} / {}", work.author, work.title),
+            Choice::AnyTitle(work) => write!(f, "{} / (any title)", work.author),
+        }
+    }
+}
+
+/// 作者・作品名・書き出しをあいまい検索して作品を選択する (複数選択可)
+///
+/// 作者名と作品名は読み (かな・ローマ字) でも検索できる。
+/// カーソルのある選択肢の書き出しは右のペインにプレビューする。
+/// `(any title)`を選択した場合はその作者の作品から`seed`でランダムに1件選ぶ
+pub fn select(works: &[Work], seed: Option<u64>) -> Result<Vec<Work>, Error> {
+    if works.is_empty() {
+        return Ok(Vec::new());
+    }
+    check_terminal(
+        io::stderr().is_terminal(),
+        io::stdin().is_terminal() || File::open("/dev/tty").is_ok(),
+    )?;
+
+    let choices = choices(works);
+    let mut picker = Picker::new(&choices);
+    let selected = {
+        let _guard = TerminalGuard::new()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+        picker.run(&mut terminal)?
+    };
+
+    Ok(selected
+        .into_iter()
+        .map(|index| resolve(works, &choices[index], seed))
+        .collect())
+}
+
+/// 対話モードに必要な端末があるか
+///
+/// 標準出力をパイプで渡せるように、画面は標準エラー出力に描画し、
+/// キー入力は標準入力 (端末でなければ`/dev/tty`) から読む
+fn check_terminal(stderr: bool, input: bool) -> Result<(), Error> {
+    if stderr && input {
+        Ok(())
+    } else {
+        Err(Error::NotTty)
+    }
+}
+
+/// 選択肢の一覧と、カーソルのある選択肢のプレビュー
+struct Picker<'a> {
+    choices: &'a [Choice<'a>],
+    /// 選択肢ごとの検索対象
+    keys: Vec<String>,
+    matcher: SkimMatcherV2,
+    query: String,
+    /// 検索語に一致する選択肢の番号 (スコア順)
+    matched: Vec<usize>,
+    state: ListState,
+    /// 選択した選択肢の番号
+    selected: BTreeSet<usize>,
+}
+
+impl<'a> Picker<'a> {
+    fn new(choices: &'a [Choice<'a>]) -> Self {
+        let mut picker = Picker {
+            choices,
+            keys: choices.iter().map(search_key).collect(),
+            matcher: SkimMatcherV2::default().ignore_case(),
+            query: String::new(),
+            matched: Vec::new(),
+            state: ListState::default(),
+            selected: BTreeSet::new(),
+        };
+        picker.update();
+        picker
+    }
+
+    /// 確定した選択肢の番号を返す
+    fn run(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<Stderr>>,
+    ) -> Result<Vec<usize>, Error> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && let Some(result) = self.handle_key(key)
+            {
+                return result;
+            }
+        }
+    }
+
+    /// キー入力を処理し、確定またはキャンセルした場合は結果を返す
+    ///
+    /// - 文字: 検索語の入力、`Backspace`: 1文字削除
+    /// - `↑`/`↓` (`Ctrl-p`/`Ctrl-n`): 移動、`Space`: 選択の切り替え
+    /// - `Enter`: 確定 (何も選択していなければカーソルのある選択肢)
+    /// - `Esc`: キャンセル、`Ctrl-c`: 中断
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Result<Vec<usize>, Error>> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('c') => return Some(Err(Error::Interrupted)),
+                KeyCode::Char('n') => self.move_cursor(1),
+                KeyCode::Char('p') => self.move_cursor(-1),
+                _ => {}
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Esc => return Some(Err(Error::Canceled)),
+            KeyCode::Enter => return self.confirm().map(Ok),
+            KeyCode::Char(' ') => self.toggle(),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.update();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// 検索語で選択肢を絞り込む (空の場合は全選択肢)
+    fn update(&mut self) {
+        self.matched = if self.query.is_empty() {
+            (0..self.choices.len()).collect()
+        } else {
+            let mut scored: Vec<(i64, usize)> = self
+                .keys
+                .iter()
+                .enumerate()
+                .filter_map(|(index, key)| {
+                    let score = self.matcher.fuzzy_match(key, &self.query)?;
+                    Some((score, index))
+                })
+                .collect();
+            scored.sort_by_key(|(score, _)| Reverse(*score));
+            scored.into_iter().map(|(_, index)| index).collect()
+        };
+        self.state.select((!self.matched.is_empty()).then_some(0));
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        if self.matched.is_empty() {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0);
+        self.state.select(Some(
+            current
+                .saturating_add_signed(delta)
+                .min(self.matched.len() - 1),
+        ));
+    }
+
+    /// カーソルのある選択肢の番号
+    fn current(&self) -> Option<usize> {
+        self.matched.get(self.state.selected()?).copied()
+    }
+
+    fn toggle(&mut self) {
+        if let Some(index) = self.current()
+            && !self.selected.remove(&index)
+        {
+            self.selected.insert(index);
+        }
+    }
+
+    /// 選択した選択肢 (一覧の順)、何も選択していなければカーソルのある選択肢
+    fn confirm(&self) -> Option<Vec<usize>> {
+        if self.selected.is_empty() {
+            self.current().map(|index| vec![index])
+        } else {
+            Some(self.selected.iter().copied().collect())
+        }
+    }
+
+    /// カーソルのある選択肢のプレビュー (`(any title)`は作者の作品名の一覧)
+    fn preview(&self) -> Vec<Line<'a>> {
+        match self.current().map(|index| &self.choices[index]) {
+            Some(Choice::Work(work)) => browse::preview(work),
+            Some(Choice::AnyTitle(first)) => {
+                let mut lines = vec![
+                    Line::from(format!("A random work by {}", first.author)),
+                    Line::default(),
+                ];
+                lines.extend(self.choices.iter().filter_map(|choice| match choice {
+                    Choice::Work(work) if work.author_id == first.author_id => {
+                        Some(Line::from(format!("- {}", work.title)))
+                    }
+                    _ => None,
+                }));
+                lines
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let [search, body, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list, preview] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(body);
+
+        let title = format!(
+            "Select works ({}/{}, {} selected)",
+            self.matched.len(),
+            self.choices.len(),
+            self.selected.len()
+        );
+        frame.render_widget(
+            Paragraph::new(self.query.as_str()).block(Block::bordered().title(title)),
+            search,
+        );
+        let width = u16::try_from(Line::from(self.query.as_str()).width()).unwrap_or(u16::MAX);
+        frame.set_cursor_position(Position::new(
+            search.x.saturating_add(1).saturating_add(width),
+            search.y + 1,
+        ));
+
+        let items = self.matched.iter().map(|index| {
+            let mark = if self.selected.contains(index) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            format!("{mark} {}", self.choices[*index])
+        });
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title("Works"))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.state,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.preview())
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Opening")),
+            preview,
+        );
+
+        frame.render_widget(
+            Line::styled(
+                "type to search author / title / opening (romaji ok)  ↑↓ move  space select  enter confirm  esc cancel",
+                Style::default().fg(Color::DarkGray),
+            ),
+            help,
+        );
+    }
+}
+
+/// 作者順に、複数の作品がある作者は`(any title)`に続けて各作品を並べる
+fn choices(works: &[Work]) -> Vec<Choice<'_>> {
+    let mut choices = Vec::new();
+    for author in works.to_vec().authors() {
+        let by_author: Vec<&Work> = works
+            .iter()
+            .filter(|work| work.author_id == author.id)
+            .collect();
+        if by_author.len() > 1 {
+            choices.push(Choice::AnyTitle(by_author[0]));
+        }
+        choices.extend(by_author.into_iter().map(Choice::Work));
+    }
+    choices
+}
+
+fn resolve(works: &[Work], choice: &Choice<'_>, seed: Option<u64>) -> Work {
+    match choice {
+        Choice::Work(work) => (*work).clone(),
+        Choice::AnyTitle(first) => works
+            .iter()
+            .filter(|work| work.author_id == first.author_id)
+            .cloned()
+            .collect::<Vec<Work>>()
+            .random(seed)
+            .swap_remove(0),
+    }
+}
+
+/// あいまい検索の対象文字列 (作者、作品名、書き出し、読み、読みのローマ字)
+fn search_key(choice: &Choice<'_>) -> String {
+    let (work, fields): (&Work, &[Field]) = match choice {
+        Choice::Work(work) => (
+            work,
+            &[
+                Field::Author,
+                Field::Title,
+                Field::Text,
+                Field::AuthorReading,
+                Field::TitleReading,
+            ],
+        ),
+        Choice::AnyTitle(work) => (work, &[Field::Author, Field::AuthorReading]),
+    };
+
+    let mut key: Vec<String> = fields
+        .iter()
+        .map(|field| field.value(work).to_string())
+        .collect();
+    for field in [Field::AuthorReading, Field::TitleReading] {
+        if fields.contains(&field) {
+            key.push(to_romaji(field.value(work)));
+        }
+    }
+    key.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::sample_works;
+
+    fn press(picker: &mut Picker<'_>, code: KeyCode) -> Option<Result<Vec<usize>, Error>> {
+        picker.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn search(picker: &mut Picker<'_>, query: &str) {
+        while !picker.query.is_empty() {
+            press(picker, KeyCode::Backspace);
+        }
+        for c in query.chars() {
+            press(picker, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_check_terminal() {
+        // 標準出力がパイプでも、標準エラー出力と入力が端末なら使える
+        assert!(check_terminal(true, true).is_ok());
+        assert!(matches!(check_terminal(false, true), Err(Error::NotTty)));
+        assert!(matches!(check_terminal(true, false), Err(Error::NotTty)));
+    }
+
+    #[test]
+    fn test_choices_any_title() {
+        let works = sample_works();
+        let choices = choices(&works);
+        let labels: Vec<String> = choices.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "夏目 漱石 / (any title)",
+                "夏目 漱石 / 坊っちゃん",
+                "夏目 漱石 / 吾輩は猫である",
+                "宮沢 賢治 / よだかの星",
+            ]
+        );
+
+        let work = resolve(&works, &choices[0], Some(1));
+        assert_eq!(work.author, "夏目 漱石");
+        assert_eq!(work.id, resolve(&works, &choices[0], Some(1)).id);
+        assert_eq!(resolve(&works, &choices[0], None).id, "752");
+    }
+
+    #[test]
+    fn test_picker() {
+        let works = sample_works();
+        let choices = choices(&works);
+        let mut picker = Picker::new(&choices);
+        assert_eq!(picker.matched, vec![0, 1, 2, 3]);
+
+        // 読みのローマ字と書き出しで検索し、カーソルのある作品の書き出し全体をプレビューする
+        search(&mut picker, "miyazawa");
+        assert_eq!(picker.matched, vec![3]);
+        search(&mut picker, "無鉄砲");
+        assert_eq!(picker.matched, vec![1]);
+        let preview: Vec<String> = picker.preview().iter().map(ToString::to_string).collect();
+        assert_eq!(preview[0], "親譲りの無鉄砲で小供の時から損ばかりして居る。");
+        assert_eq!(preview[2], "夏目 漱石『坊っちゃん』");
+
+        // 何も選択していなければカーソルのある選択肢を確定する
+        assert_eq!(
+            press(&mut picker, KeyCode::Enter).map(Result::ok),
+            Some(Some(vec![1]))
+        );
+
+        // 選択は一覧の順に返す
+        search(&mut picker, "");
+        press(&mut picker, KeyCode::Down);
+        press(&mut picker, KeyCode::Down);
+        press(&mut picker, KeyCode::Char(' '));
+        press(&mut picker, KeyCode::Up);
+        press(&mut picker, KeyCode::Up);
+        press(&mut picker, KeyCode::Char(' '));
+        assert_eq!(
+            press(&mut picker, KeyCode::Enter).map(Result::ok),
+            Some(Some(vec![0, 2]))
+        );
+        let preview: Vec<String> = picker.preview().iter().map(ToString::to_string).collect();
+        assert_eq!(preview[2..], ["- 坊っちゃん", "- 吾輩は猫である"]);
+
+        assert!(matches!(
+            press(&mut picker, KeyCode::Esc),
+            Some(Err(Error::Canceled))
+        ));
+        assert!(matches!(
+            picker.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Err(Error::Interrupted))
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::to_string;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::models::{Author, Field, Work};
+
+/// 一覧の並び順
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sort {
+    /// 名前順
+    #[default]
+    Name,
+    /// 読み順 (読みがない場合は末尾に名前順)
+    Reading,
+    /// 作品数の多い順
+    Count,
+}
+
+/// 一覧の出力形式
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ListFormat {
+    #[default]
+    Plain,
+    Json,
+}
+
+/// 作者または作品名と、その作品数
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Entry {
+    /// 人物ID (作者の一覧のみ)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub reading: Option<String>,
+    pub count: usize,
+}
+
+/// 作者の一覧 (人物IDごとに集計する)
+pub fn authors(works: &[Work], sort: Sort) -> Vec<Entry> {
+    entries(
+        works,
+        sort,
+        Some(Field::AuthorId),
+        Field::Author,
+        Field::AuthorReading,
+    )
+}
+
+/// 作品名の一覧
+pub fn titles(works: &[Work], sort: Sort) -> Vec<Entry> {
+    entries(works, sort, None, Field::Title, Field::TitleReading)
+}
+
+fn entries(
+    works: &[Work],
+    sort: Sort,
+    id: Option<Field>,
+    name: Field,
+    reading: Field,
+) -> Vec<Entry> {
+    // 作者名 (作品名) と人物IDの順に並べる (`Works::authors`と同じ順)
+    let mut groups: BTreeMap<(&str, Option<&str>), Entry> = BTreeMap::new();
+    for work in works {
+        let key = (name.value(work), id.map(|id| id.value(work)));
+        groups
+            .entry(key)
+            .and_modify(|entry| entry.count += 1)
+            .or_insert_with(|| {
+                let reading = reading.value(work);
+                Entry {
+                    id: key.1.map(str::to_string),
+                    name: key.0.to_string(),
+                    reading: (!reading.is_empty()).then(|| reading.to_string()),
+                    count: 1,
+                }
+            });
+    }
+
+    let mut entries: Vec<Entry> = groups.into_values().collect();
+    match sort {
+        Sort::Name => {}
+        Sort::Reading => entries.sort_by(|a, b| match (&a.reading, &b.reading) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }),
+        Sort::Count => entries.sort_by_key(|entry| Reverse(entry.count)),
+    }
+    entries
+}
+
+/// 一覧を出力する (作者は人物IDも、`count`が真の場合は作品数も出力する)
+pub fn print(entries: &[Entry], format: ListFormat, count: bool) {
+    match format {
+        ListFormat::Plain => {
+            for entry in entries {
+                let mut columns = vec![entry.name.clone()];
+                columns.extend(entry.id.clone());
+                if count {
+                    columns.push(entry.count.to_string());
+                }
+                println!("{}", columns.join("\t"));
+            }
+        }
+        ListFormat::Json => {
+            // 作品数なしの場合、作者は`{id, name}`、作品名は文字列の配列
+            let json = if count {
+                to_string(entries)
+            } else if entries.iter().all(|entry| entry.id.is_some()) {
+                to_string(
+                    &entries
+                        .iter()
+                        .map(|entry| Author {
+                            id: entry.id.clone().unwrap_or_default(),
+                            name: entry.name.clone(),
+                        })
+                        .collect::<Vec<Author>>(),
+                )
+            } else {
+                to_string(&entries.iter().map(|entry| &entry.name).collect::<Vec<_>>())
+            };
+            println!("{}", json.expect("Failed to serialize to JSON"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{sample_work, sample_works};
+
+    #[test]
+    fn test_authors_sort() {
+        let mut works = sample_works();
+        works.push(sample_work(
+            "45327",
+            "中原 中也",
+            "219",
+            "作家と孤独",
+            "インテリは蒼ざめてゐる。",
+        ));
+
+        let names = |entries: Vec<Entry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.name).collect()
+        };
+        assert_eq!(
+            names(authors(&works, Sort::Name)),
+            vec!["中原 中也", "夏目 漱石", "宮沢 賢治"]
+        );
+        assert_eq!(
+            names(authors(&works, Sort::Reading)),
+            vec!["夏目 漱石", "宮沢 賢治", "中原 中也"]
+        );
+        assert_eq!(
+            authors(&works, Sort::Count)[0],
+            Entry {
+                id: Some("148".to_string()),
+                name: "夏目 漱石".to_string(),
+                reading: Some("なつめ そうせき".to_string()),
+                count: 2,
+            }
+        );
+        assert_eq!(
+            names(titles(&works[..3], Sort::Reading)),
+            vec!["坊っちゃん", "よだかの星", "吾輩は猫である"]
+        );
+    }
+}
@@ -1,36 +1,123 @@
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, Subcommand};
 use flate2::read::MultiGzDecoder;
-use inquire::{InquireError, Select};
-use std::io::Read;
+use std::env;
+use std::io::{IsTerminal, Read};
+use std::net::IpAddr;
+use std::path::PathBuf;
 
+mod browse;
+mod format;
+mod index;
+mod interactive;
+mod listing;
 mod models;
-use models::{Format, Query, QueryKey, Work, Works};
+mod pretty;
+mod romaji;
+mod server;
+mod stats;
+mod template;
+use index::Index;
+use models::{Field, Format, JsonShape, Metadata, PrintOptions, Query, Work, Works};
+use pretty::{Border, Style};
 
 fn main() {
     let args = Args::parse();
     if let Err(e) = args.validate() {
         e.exit();
     }
+    if args.dataset_info {
+        models::MANIFEST.print(&args.format);
+        return;
+    }
+    let template = args.template().unwrap_or_else(|e| e.exit());
 
     let bytes = include_bytes!("resources/data.csv.gz");
     let works = read(bytes);
 
-    let selected_query: Option<Vec<Query>> = if args.interactive {
-        select(&works)
+    match &args.command {
+        Some(Command::Serve { host, port }) => {
+            if let Err(e) = server::serve(&works, *host, *port) {
+                eprintln!("Failed to start server: {e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Search { terms, limit }) => {
+            search(&works, terms, *limit);
+            return;
+        }
+        Some(Command::Authors { list }) => {
+            let entries = listing::authors(&works.filter(&list.query), list.sort);
+            listing::print(&entries, list.format, list.count);
+            return;
+        }
+        Some(Command::Titles {
+            author,
+            author_id,
+            list,
+        }) => {
+            let mut queries = list.query.clone();
+            queries.extend(author.as_deref().map(Query::author));
+            queries.extend(author_id.as_deref().map(Query::author_id));
+            let entries = listing::titles(&works.filter(&queries), list.sort);
+            listing::print(&entries, list.format, list.count);
+            return;
+        }
+        Some(Command::Stats { query, format }) => {
+            stats::Stats::new(&works.filter(query)).print(*format);
+            return;
+        }
+        Some(Command::Browse) => {
+            match browse::browse(&works) {
+                Ok(Some(work)) => vec![work].print(&Format::Plain, &PrintOptions::default()),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Failed to start browser: {e}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        None => {}
+    }
+
+    let seed = (!args.no_random).then(|| args.seed.unwrap_or_else(rand::random));
+    let matched = works.filter(&args.query);
+    // 対話モードでは`--query`で絞り込んだ作品から選択したものをそのまま出力
+    let output = if args.interactive {
+        interactive::select(&matched, seed).unwrap_or_else(|e| exit_interactive(&e))
     } else {
-        None
+        matched
+            .random(seed)
+            .take(if args.all { works.len() } else { args.number })
     };
 
-    works
-        .filter(&selected_query.unwrap_or(args.query))
-        .random(!args.no_random)
-        .take(if args.all { works.len() } else { args.number })
-        .print(&args.format, args.template.as_ref());
+    output.print(
+        &args.format,
+        &PrintOptions {
+            template: template.as_deref(),
+            fields: &args.fields,
+            header: args.header,
+            json_shape: args.json_shape,
+            metadata: Metadata::new(matched.len(), seed),
+            ruby: args.ruby,
+            height: args.height.unwrap_or(format::TATEGAKI_HEIGHT),
+            style: Style {
+                border: args.border,
+                width: args.width.unwrap_or_else(terminal_width),
+                color: color_enabled(),
+            },
+        },
+    );
 }
 
 #[derive(Parser, Debug)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(short, long, default_value_t = 1, help = "Number to output")]
     pub number: usize,
 
@@ -46,10 +133,17 @@ pub struct Args {
     #[arg(long, default_value_t = false, help = "Disable randomization")]
     pub no_random: bool,
 
+    #[arg(
+        long,
+        conflicts_with = "no_random",
+        help = "Seed for randomization [conflicts with --no-random]"
+    )]
+    pub seed: Option<u64>,
+
     #[arg(
         short,
         long,
-        help = "Filter queries [format: key=value] [possible keys: author, title, text]",
+        help = "Filter queries [format: key=value] [possible keys: author, author_id, title, text]",
         value_parser
     )]
     pub query: Vec<Query>,
@@ -58,8 +152,8 @@ pub struct Args {
         short,
         long,
         default_value_t = false,
-        conflicts_with = "query",
-        help = "Interactive selection mode [conflicts with --query]"
+        conflicts_with_all = ["number", "all"],
+        help = "Interactive fuzzy search and multi-selection mode [choices are filtered by --query] [conflicts with --number and --all]"
     )]
     interactive: bool,
 
@@ -67,46 +161,292 @@ pub struct Args {
     #[clap(value_enum, default_value_t=Format::Plain)]
     pub format: Format,
 
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print information about the embedded dataset (source commit, extraction date, record counts, checksum)"
+    )]
+    pub dataset_info: bool,
+
     #[arg(
         short,
         long,
-        value_parser = template_validator,
-        help = "Template only for 'quote' format [possible laceholders: {author}, {title}, {text}, {url}. example: '{text} - {author} ({title})']"
+        value_parser = template::validate,
+        help = "Output template [possible placeholders: {id}, {author}, {author_id}, {title}, {text}, {url}, {card_url}, {ruby}, {citation}. example: '{text} - {author} ({title})'] [csv/json: placeholders select columns/keys]"
     )]
     pub template: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "template",
+        help = "Read output template from file [conflicts with --template]"
+    )]
+    pub template_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["template", "template_file"],
+        help = "Use named template [built-in: default, dash, slack, url] [user: $XDG_CONFIG_HOME/kakidashi/templates/NAME.tmpl]"
+    )]
+    pub template_name: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Fields to output in order, only for 'csv', 'tsv', 'json' and 'jsonl' formats [example: author,text]"
+    )]
+    pub fields: Vec<Field>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Output header row, only for 'csv' and 'tsv' formats"
+    )]
+    pub header: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = JsonShape::Auto,
+        help = "Shape of 'json' format output [auto: object if exactly one, otherwise array]"
+    )]
+    pub json_shape: JsonShape,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Render ruby, only for 'html' and 'latex' formats"
+    )]
+    pub ruby: bool,
+
+    #[arg(
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Column height, only for 'tategaki' format [default: 20]"
+    )]
+    pub height: Option<usize>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Border::None,
+        help = "Border style, only for 'pretty' format"
+    )]
+    pub border: Border,
+
+    #[arg(
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Output width, only for 'pretty' format [default: terminal width]"
+    )]
+    pub width: Option<usize>,
 }
 
-fn template_validator(s: &str) -> Result<String, String> {
-    if s.contains("{author}")
-        || s.contains("{title}")
-        || s.contains("{text}")
-        || s.contains("{url}")
-    {
-        Ok(s.to_string())
-    } else {
-        Err(
-            "Template must contain at least one of the placeholders: {author}, {title}, {text}, {url}"
-                .to_string(),
-        )
-    }
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Serve a JSON API over HTTP [GET /random, /works/{id}, /authors, /authors/{id}/titles, /daily]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1", value_parser = server::parse_host, help = "Loopback address to listen on [127.0.0.1, ::1, localhost]")]
+        host: IpAddr,
+
+        #[arg(short, long, default_value_t = 8080, help = "Port to listen on")]
+        port: u16,
+    },
+
+    /// Full-text search over author, title and opening, ranked by relevance
+    Search {
+        #[arg(required = true, help = "Search terms [all terms must match]")]
+        terms: Vec<String>,
+
+        #[arg(short, long, default_value_t = 20, help = "Maximum number of results")]
+        limit: usize,
+    },
+
+    /// Browse authors, titles and openings in a full-screen terminal UI
+    Browse,
+
+    /// List authors
+    Authors {
+        #[command(flatten)]
+        list: ListArgs,
+    },
+
+    /// List titles of an author
+    Titles {
+        #[arg(
+            long,
+            required_unless_present = "author_id",
+            conflicts_with = "author_id",
+            help = "Author name to list titles of [partial match]"
+        )]
+        author: Option<String>,
+
+        #[arg(long, help = "Author ID (人物ID) to list titles of")]
+        author_id: Option<String>,
+
+        #[command(flatten)]
+        list: ListArgs,
+    },
+
+    /// Show corpus statistics
+    Stats {
+        #[arg(
+            short,
+            long,
+            help = "Filter queries [format: key=value] [possible keys: author, author_id, title, text]",
+            value_parser
+        )]
+        query: Vec<Query>,
+
+        #[arg(short, long, value_enum, default_value_t = listing::ListFormat::Plain, help = "Output format [plain: table]")]
+        format: listing::ListFormat,
+    },
+}
+
+/// `authors`/`titles`サブコマンドの共通オプション
+#[derive(clap::Args, Debug)]
+pub struct ListArgs {
+    #[arg(
+        short,
+        long,
+        help = "Filter queries [format: key=value] [possible keys: author, author_id, title, text]",
+        value_parser
+    )]
+    pub query: Vec<Query>,
+
+    #[arg(short, long, default_value_t = false, help = "Output number of works")]
+    pub count: bool,
+
+    #[arg(short, long, value_enum, default_value_t = listing::Sort::Name, help = "Sort order")]
+    pub sort: listing::Sort,
+
+    #[arg(short, long, value_enum, default_value_t = listing::ListFormat::Plain, help = "Output format")]
+    pub format: listing::ListFormat,
 }
 
 impl Args {
     fn validate(&self) -> Result<(), clap::Error> {
-        if self.template.is_some() {
-            match self.format {
-                Format::Quote => Ok(()),
-                _ => Err(Self::command().error(
-                    clap::error::ErrorKind::ArgumentConflict,
-                    "--template can only be used with --format quote",
-                )),
-            }
-        } else {
-            Ok(())
+        let tabular = matches!(self.format, Format::Csv | Format::Tsv);
+        if !self.fields.is_empty() && !self.structured() {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--fields can only be used with --format csv, tsv, json or jsonl",
+            ));
+        }
+        let templated =
+            self.template.is_some() || self.template_file.is_some() || self.template_name.is_some();
+        if templated && !matches!(self.format, Format::Plain | Format::Quote) && !self.structured()
+        {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--template, --template-file and --template-name can only be used with --format plain, quote, csv, tsv, json or jsonl",
+            ));
+        }
+        if self.header && !tabular {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--header can only be used with --format csv or tsv",
+            ));
+        }
+        if self.ruby && !matches!(self.format, Format::Html | Format::Latex) {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--ruby can only be used with --format html or latex",
+            ));
+        }
+        if self.height.is_some() && !matches!(self.format, Format::Tategaki) {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--height can only be used with --format tategaki",
+            ));
         }
+        let pretty = matches!(self.format, Format::Pretty);
+        if (self.border != Border::None || self.width.is_some()) && !pretty {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--border and --width can only be used with --format pretty",
+            ));
+        }
+        if !matches!(self.json_shape, JsonShape::Auto) && !matches!(self.format, Format::Json) {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--json-shape can only be used with --format json",
+            ));
+        }
+        Ok(())
+    }
+
+    /// `--template`/`--template-file`/`--template-name`のいずれかからテンプレートを解決する
+    fn template(&self) -> Result<Option<String>, clap::Error> {
+        let template = if let Some(path) = &self.template_file {
+            template::read_file(path).map(Some)
+        } else if let Some(name) = &self.template_name {
+            template::preset(name).map(Some)
+        } else {
+            Ok(self.template.clone())
+        };
+        let template = template.and_then(|template| match &template {
+            Some(columns) if self.structured() => {
+                template::validate_columns(columns).map(|()| template)
+            }
+            _ => Ok(template),
+        });
+
+        template.map_err(|e| Self::command().error(clap::error::ErrorKind::InvalidValue, e))
+    }
+
+    /// 列/キーを持つ形式 (`csv`/`tsv`/`json`/`jsonl`)
+    fn structured(&self) -> bool {
+        matches!(
+            self.format,
+            Format::Csv | Format::Tsv | Format::Json | Format::Jsonl
+        )
     }
 }
 
+/// 全文検索の結果を`quote`形式で一致箇所を強調して出力する
+fn search(works: &[Work], terms: &[String], limit: usize) {
+    let color = color_enabled();
+    for hit in Index::new(works).search(works, terms, limit) {
+        let work = &works[hit.index];
+        println!(
+            "{}｜{}『{}』",
+            index::highlight(&work.text, terms, color),
+            index::highlight(&work.author, terms, color),
+            index::highlight(&work.title, terms, color)
+        );
+    }
+}
+
+/// 対話モードのキャンセルやエラーで終了する (Ctrl-Cは130、それ以外は1)
+fn exit_interactive(error: &interactive::Error) -> ! {
+    match error {
+        interactive::Error::Canceled => eprintln!("Cancelled"),
+        interactive::Error::Interrupted => std::process::exit(130),
+        interactive::Error::NotTty => eprintln!("Interactive mode requires a terminal"),
+        interactive::Error::Io(e) => eprintln!("Interactive mode failed: {e}"),
+    }
+    std::process::exit(1);
+}
+
+/// 端末幅 (取得できない場合は`COLUMNS`環境変数、それもなければデフォルト値)
+fn terminal_width() -> usize {
+    crossterm::terminal::size()
+        .ok()
+        .map(|(columns, _)| usize::from(columns))
+        .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+        .filter(|width| *width > 0)
+        .unwrap_or(pretty::DEFAULT_WIDTH)
+}
+
+/// 標準出力が端末かつ`NO_COLOR`が未設定の場合のみ着色する
+fn color_enabled() -> bool {
+    std::io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
 fn read(bytes: &[u8]) -> Vec<Work> {
     let mut decompressed = Vec::new();
     MultiGzDecoder::new(bytes)
@@ -119,27 +459,3 @@ fn read(bytes: &[u8]) -> Vec<Work> {
 
     csv.deserialize().filter_map(Result::ok).collect()
 }
-
-fn select(works: &Vec<Work>) -> Option<Vec<Query>> {
-    let selected_author = Select::new("Select author:", works.authors()).prompt();
-    let selected_title = if let Ok(author) = &selected_author {
-        Select::new("Select title:", works.titles(author)).prompt()
-    } else {
-        Err(InquireError::OperationCanceled)
-    };
-
-    if let (Ok(author), Ok(title)) = (selected_author, selected_title) {
-        Some(vec![
-            Query {
-                key: QueryKey::Author,
-                value: author,
-            },
-            Query {
-                key: QueryKey::Title,
-                value: title,
-            },
-        ])
-    } else {
-        None
-    }
-}
@@ -1,34 +1,60 @@
 use clap::ValueEnum;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::to_string;
 use std::str::FromStr;
+use std::sync::LazyLock;
+
+use crate::pretty::{self, Style};
+use crate::{format, template};
 
 // 作品データ
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Work {
+    pub id: String,
     pub author: String,
+    /// 人物ID (同名・部分一致する作者を区別する)
+    pub author_id: String,
     pub title: String,
     pub text: String,
     url: Option<String>,
+    ruby: Option<String>,
+    author_reading: Option<String>,
+    title_reading: Option<String>,
+    /// 図書カードのURL
+    card_url: Option<String>,
+    /// 書き出しの抽出元 (`txt`: テキストファイル、`xhtml`: XHTMLファイル)
+    source: Option<String>,
+    /// 底本 (書名、出版社、発行年月日)
+    base_text: Option<String>,
+    /// 初出
+    first_published: Option<String>,
+    /// 入力者
+    inputter: Option<String>,
+    /// 校正者
+    proofreader: Option<String>,
 }
 
 pub trait Works {
-    fn random(&self, random: bool) -> Vec<Work>;
+    fn random(&self, seed: Option<u64>) -> Vec<Work>;
     fn take(&self, n: usize) -> Vec<Work>;
     fn filter(&self, queries: &[Query]) -> Vec<Work>;
-    fn print(&self, format: &Format, template: Option<&String>);
-    fn authors(&self) -> Vec<String>;
-    fn titles(&self, author: &str) -> Vec<String>;
+    fn print(&self, format: &Format, options: &PrintOptions<'_>);
+    fn to_json(&self, options: &PrintOptions<'_>) -> String;
+    fn authors(&self) -> Vec<Author>;
+    fn titles(&self, author_id: &str) -> Vec<String>;
 }
 
 impl Works for Vec<Work> {
-    fn random(&self, random: bool) -> Vec<Work> {
-        if !random {
+    fn random(&self, seed: Option<u64>) -> Vec<Work> {
+        let Some(seed) = seed else {
             return self.clone();
-        }
+        };
 
-        let mut rng = rand::rng();
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut works = self.clone();
         works.shuffle(&mut rng);
         works
@@ -40,69 +66,139 @@ impl Works for Vec<Work> {
 
     fn filter(&self, queries: &[Query]) -> Vec<Work> {
         self.iter()
-            .filter(|work| {
-                queries.iter().all(|query| match query.key {
-                    QueryKey::Author => work.author.contains(&query.value),
-                    QueryKey::Title => work.title.contains(&query.value),
-                    QueryKey::Text => work.text.contains(&query.value),
-                })
-            })
+            .filter(|work| queries.iter().all(|query| query.matches(work)))
             .cloned()
             .collect()
     }
 
-    fn print(&self, format: &Format, template: Option<&String>) {
-        if self.is_empty() {
+    fn print(&self, format: &Format, options: &PrintOptions<'_>) {
+        let stable_json =
+            matches!(format, Format::Json) && !matches!(options.json_shape, JsonShape::Auto);
+        if self.is_empty() && !stable_json {
             return;
         }
 
+        let template = options.template;
+        let fields = options.fields();
+
         match format {
             Format::Plain => {
                 for work in self {
-                    println!("{}", work.text);
+                    match template {
+                        Some(template) => println!("{}", template::render(template, work)),
+                        None => println!("{}", work.text),
+                    }
                 }
             }
             Format::Quote => {
-                let template = template.map_or("{text}｜{author}『{title}』", |v| v);
+                let template = template.unwrap_or(template::DEFAULT_TEMPLATE);
                 for work in self {
-                    let output = template
-                        .replace("\\n", "\n")
-                        .replace("{author}", &work.author)
-                        .replace("{title}", &work.title)
-                        .replace("{text}", &work.text)
-                        .replace("{url}", work.url.as_deref().unwrap_or(""));
-                    println!("{output}");
+                    println!("{}", template::render(template, work));
                 }
             }
-            Format::Csv => {
-                let mut writer = csv::Writer::from_writer(std::io::stdout());
+            Format::Csv | Format::Tsv => {
+                let delimiter = if matches!(format, Format::Tsv) {
+                    b'\t'
+                } else {
+                    b','
+                };
+                let mut writer = csv::WriterBuilder::new()
+                    .delimiter(delimiter)
+                    .from_writer(std::io::stdout());
+                if options.header {
+                    writer
+                        .write_record(fields.iter().map(|field| field.name()))
+                        .expect("Failed to write CSV");
+                }
                 for work in self {
-                    writer.serialize(work).expect("Failed to write CSV");
+                    writer
+                        .write_record(fields.iter().map(|field| field.value(work)))
+                        .expect("Failed to write CSV");
                 }
                 writer.flush().expect("Failed to flush CSV writer");
             }
-            Format::Json => {
-                let json = if self.len() == 1 {
-                    to_string(&self[0]).expect("Failed to serialize to JSON")
-                } else {
-                    to_string(&self).expect("Failed to serialize to JSON")
-                };
-                println!("{json}");
+            Format::Json => println!("{}", self.to_json(options)),
+            Format::Markdown | Format::Html | Format::Latex | Format::Tategaki | Format::Pretty => {
+                let blocks: Vec<String> = self
+                    .iter()
+                    .map(|work| match format {
+                        Format::Markdown => format::markdown(work),
+                        Format::Html => format::html(work, options.ruby),
+                        Format::Latex => format::latex(work, options.ruby),
+                        Format::Tategaki => format::tategaki(work, options.height),
+                        _ => pretty::pretty(work, options.style),
+                    })
+                    .collect();
+                println!("{}", blocks.join("\n\n"));
+            }
+            Format::Fortune => {
+                for work in self {
+                    println!("{}", format::fortune(work));
+                }
+            }
+            Format::Bibtex => {
+                let entries: Vec<String> = self.iter().map(format::bibtex).collect();
+                println!("{}", entries.join("\n\n"));
+            }
+            Format::CslJson => {
+                let items: Vec<format::CslItem<'_>> = self.iter().map(format::csl).collect();
+                println!(
+                    "{}",
+                    to_string(&items).expect("Failed to serialize to JSON")
+                );
+            }
+            Format::Jsonl => {
+                for work in self {
+                    let json = to_string(&Selected {
+                        work,
+                        fields: &fields,
+                    })
+                    .expect("Failed to serialize to JSON");
+                    println!("{json}");
+                }
             }
         }
     }
 
-    fn authors(&self) -> Vec<String> {
-        let mut authors: Vec<String> = self.iter().map(|work| work.author.clone()).collect();
-        authors.sort();
+    fn to_json(&self, options: &PrintOptions<'_>) -> String {
+        let fields = options.fields();
+        let selected: Vec<Selected<'_>> = self
+            .iter()
+            .map(|work| Selected {
+                work,
+                fields: &fields,
+            })
+            .collect();
+        match options.json_shape {
+            JsonShape::Auto if selected.len() == 1 => to_string(&selected[0]),
+            JsonShape::Auto | JsonShape::Array => to_string(&selected),
+            JsonShape::Envelope => to_string(&Envelope {
+                metadata: &options.metadata,
+                works: &selected,
+            }),
+        }
+        .expect("Failed to serialize to JSON")
+    }
+
+    /// 作者の一覧 (作者名順、同名の作者は人物ID順)
+    fn authors(&self) -> Vec<Author> {
+        let mut authors: Vec<Author> = self
+            .iter()
+            .map(|work| Author {
+                id: work.author_id.clone(),
+                name: work.author.clone(),
+            })
+            .collect();
+        authors.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
         authors.dedup();
         authors
     }
 
-    fn titles(&self, author: &str) -> Vec<String> {
+    /// 人物IDが一致する作者の作品名
+    fn titles(&self, author_id: &str) -> Vec<String> {
         let mut titles: Vec<String> = self
             .iter()
-            .filter(|work| work.author.contains(author))
+            .filter(|work| work.author_id == author_id)
             .map(|work| work.title.clone())
             .collect();
         titles.sort();
@@ -111,6 +207,13 @@ impl Works for Vec<Work> {
     }
 }
 
+/// 作者 (人物IDで識別する)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Author {
+    pub id: String,
+    pub name: String,
+}
+
 /// CLIオプション
 #[derive(Clone, Debug)]
 pub struct Query {
@@ -118,13 +221,52 @@ pub struct Query {
     pub(crate) value: String,
 }
 
-#[derive(ValueEnum, Clone, Debug, PartialEq)]
+impl Query {
+    /// 作者名で絞り込むクエリ
+    pub fn author(author: &str) -> Self {
+        Query {
+            key: QueryKey::Author,
+            value: author.to_string(),
+        }
+    }
+
+    /// 人物IDで絞り込むクエリ
+    pub fn author_id(author_id: &str) -> Self {
+        Query {
+            key: QueryKey::AuthorId,
+            value: author_id.to_string(),
+        }
+    }
+
+    /// 人物IDは完全一致、それ以外は部分一致で照合する
+    pub fn matches(&self, work: &Work) -> bool {
+        match self.key {
+            QueryKey::AuthorId => work.author_id == self.value,
+            key => key.value(work).contains(&self.value),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) enum QueryKey {
     Author,
+    #[value(name = "author_id")]
+    AuthorId,
     Title,
     Text,
 }
 
+impl QueryKey {
+    pub fn value(self, work: &Work) -> &str {
+        match self {
+            QueryKey::Author => &work.author,
+            QueryKey::AuthorId => &work.author_id,
+            QueryKey::Title => &work.title,
+            QueryKey::Text => &work.text,
+        }
+    }
+}
+
 impl FromStr for Query {
     type Err = String;
 
@@ -132,8 +274,9 @@ impl FromStr for Query {
         let (key, value) = s
             .split_once('=')
             .ok_or("Invalid filter format. Use key=value.")?;
-        let key = QueryKey::from_str(key, true)
-            .map_err(|_| "Invalid filter key. Valid keys: author, title.".to_string())?;
+        let key = QueryKey::from_str(key, true).map_err(|_| {
+            "Invalid filter key. Valid keys: author, author_id, title, text.".to_string()
+        })?;
         Ok(Query {
             key,
             value: value.to_string(),
@@ -141,10 +284,396 @@ impl FromStr for Query {
     }
 }
 
+/// 出力オプション
+#[derive(Debug, Default)]
+pub struct PrintOptions<'a> {
+    pub template: Option<&'a str>,
+    pub fields: &'a [Field],
+    pub header: bool,
+    pub json_shape: JsonShape,
+    pub metadata: Metadata,
+    pub ruby: bool,
+    pub height: usize,
+    pub style: Style,
+}
+
+impl PrintOptions<'_> {
+    /// `csv`/`tsv`/`json`/`jsonl`形式の出力項目
+    ///
+    /// `--fields` > テンプレートのプレースホルダ > 全項目 の順に優先する
+    fn fields(&self) -> Vec<Field> {
+        if !self.fields.is_empty() {
+            self.fields.to_vec()
+        } else if let Some(template) = self.template {
+            template::fields(template)
+        } else {
+            Field::DEFAULT.to_vec()
+        }
+    }
+}
+
+/// 出力項目
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Id,
+    Author,
+    #[value(name = "author_id")]
+    AuthorId,
+    Title,
+    Text,
+    Url,
+    Ruby,
+    #[value(name = "author_reading")]
+    AuthorReading,
+    #[value(name = "title_reading")]
+    TitleReading,
+    #[value(name = "card_url")]
+    CardUrl,
+    Source,
+    #[value(name = "base_text")]
+    BaseText,
+    #[value(name = "first_published")]
+    FirstPublished,
+    Inputter,
+    Proofreader,
+}
+
+impl Field {
+    pub const ALL: [Field; 15] = [
+        Field::Id,
+        Field::Author,
+        Field::AuthorId,
+        Field::Title,
+        Field::Text,
+        Field::Url,
+        Field::Ruby,
+        Field::AuthorReading,
+        Field::TitleReading,
+        Field::CardUrl,
+        Field::Source,
+        Field::BaseText,
+        Field::FirstPublished,
+        Field::Inputter,
+        Field::Proofreader,
+    ];
+
+    /// 項目未指定時の出力項目
+    pub const DEFAULT: [Field; 6] = [
+        Field::Id,
+        Field::Author,
+        Field::AuthorId,
+        Field::Title,
+        Field::Text,
+        Field::Url,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Id => "id",
+            Field::Author => "author",
+            Field::AuthorId => "author_id",
+            Field::Title => "title",
+            Field::Text => "text",
+            Field::Url => "url",
+            Field::Ruby => "ruby",
+            Field::AuthorReading => "author_reading",
+            Field::TitleReading => "title_reading",
+            Field::CardUrl => "card_url",
+            Field::Source => "source",
+            Field::BaseText => "base_text",
+            Field::FirstPublished => "first_published",
+            Field::Inputter => "inputter",
+            Field::Proofreader => "proofreader",
+        }
+    }
+
+    pub fn placeholder(self) -> String {
+        format!("{{{}}}", self.name())
+    }
+
+    pub fn value(self, work: &Work) -> &str {
+        match self {
+            Field::Id => &work.id,
+            Field::Author => &work.author,
+            Field::AuthorId => &work.author_id,
+            Field::Title => &work.title,
+            Field::Text => &work.text,
+            Field::Url => work.url.as_deref().unwrap_or(""),
+            Field::Ruby => work.ruby.as_deref().unwrap_or(""),
+            Field::AuthorReading => work.author_reading.as_deref().unwrap_or(""),
+            Field::TitleReading => work.title_reading.as_deref().unwrap_or(""),
+            Field::CardUrl => work.card_url.as_deref().unwrap_or(""),
+            Field::Source => work.source.as_deref().unwrap_or(""),
+            Field::BaseText => work.base_text.as_deref().unwrap_or(""),
+            Field::FirstPublished => work.first_published.as_deref().unwrap_or(""),
+            Field::Inputter => work.inputter.as_deref().unwrap_or(""),
+            Field::Proofreader => work.proofreader.as_deref().unwrap_or(""),
+        }
+    }
+}
+
+/// 出力に付随するメタデータ (`--json-shape envelope`で出力)
+#[derive(Serialize, Debug, Default)]
+pub struct Metadata {
+    /// フィルタに一致した件数
+    pub total: usize,
+    /// ランダム化に使用したシード (`--no-random`時は`null`)
+    pub seed: Option<u64>,
+    /// kakidashiのバージョン (データセットは`dataset`で特定する)
+    pub cli_version: &'static str,
+    /// 埋め込みデータセットのマニフェスト
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<&'static Manifest>,
+}
+
+impl Metadata {
+    pub fn new(total: usize, seed: Option<u64>) -> Self {
+        Metadata {
+            total,
+            seed,
+            cli_version: env!("CARGO_PKG_VERSION"),
+            dataset: Some(&MANIFEST),
+        }
+    }
+}
+
+/// 埋め込みデータセットのマニフェスト (`cargo xtask extract`で生成)
+pub static MANIFEST: LazyLock<Manifest> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("resources/manifest.json")).expect("Failed to parse manifest")
+});
+
+/// データセットの抽出元と内容の要約 (不具合報告でデータセットを特定するため)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    /// 抽出元の青空文庫リポジトリのコミット
+    pub aozorabunko_commit: Option<String>,
+    /// 抽出日時 (RFC 3339)
+    pub extracted_at: String,
+    /// 抽出した作品数 (書き出しが空の作品を含む)
+    pub records: usize,
+    /// 埋め込んだ作品数
+    pub records_with_text: usize,
+    pub extractor_version: String,
+    /// 埋め込んだ圧縮CSVのSHA-256
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// `--dataset-info`の出力 (`json`/`jsonl`形式以外は1行1項目)
+    pub fn print(&self, format: &Format) {
+        if matches!(format, Format::Json | Format::Jsonl) {
+            println!("{}", to_string(self).expect("Failed to serialize to JSON"));
+            return;
+        }
+        println!(
+            "aozorabunko commit: {}",
+            self.aozorabunko_commit.as_deref().unwrap_or("unknown")
+        );
+        println!("extracted at: {}", self.extracted_at);
+        println!(
+            "records: {} ({} with text)",
+            self.records, self.records_with_text
+        );
+        println!("extractor version: {}", self.extractor_version);
+        println!("sha256: {}", self.sha256);
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    #[serde(flatten)]
+    metadata: &'a Metadata,
+    works: &'a [Selected<'a>],
+}
+
+/// 指定項目のみを出力順に保ってシリアライズする
+struct Selected<'a> {
+    work: &'a Work,
+    fields: &'a [Field],
+}
+
+impl Serialize for Selected<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for field in self.fields {
+            let value = field.value(self.work);
+            match field {
+                // 任意項目は空の場合null
+                Field::Url
+                | Field::Ruby
+                | Field::AuthorReading
+                | Field::TitleReading
+                | Field::CardUrl
+                | Field::Source
+                | Field::BaseText
+                | Field::FirstPublished
+                | Field::Inputter
+                | Field::Proofreader => {
+                    map.serialize_entry(field.name(), &Some(value).filter(|v| !v.is_empty()))?;
+                }
+                _ => map.serialize_entry(field.name(), value)?,
+            }
+        }
+        map.end()
+    }
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 pub enum Format {
     Plain,
     Quote,
     Csv,
+    Tsv,
     Json,
+    Jsonl,
+    Markdown,
+    Html,
+    Latex,
+    Tategaki,
+    Pretty,
+    Fortune,
+    Bibtex,
+    CslJson,
+}
+
+/// `json`形式の出力形
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum JsonShape {
+    /// 1件ならオブジェクト、それ以外は配列
+    #[default]
+    Auto,
+    /// 常に配列
+    Array,
+    /// メタデータと配列を含むオブジェクト
+    Envelope,
+}
+
+/// テスト用の作品 (任意の列は空)
+#[cfg(test)]
+pub(crate) fn sample_work(
+    id: &str,
+    author: &str,
+    author_id: &str,
+    title: &str,
+    text: &str,
+) -> Work {
+    Work {
+        id: id.to_string(),
+        author: author.to_string(),
+        author_id: author_id.to_string(),
+        title: title.to_string(),
+        text: text.to_string(),
+        url: None,
+        ruby: None,
+        author_reading: None,
+        title_reading: None,
+        card_url: None,
+        source: None,
+        base_text: None,
+        first_published: None,
+        inputter: None,
+        proofreader: None,
+    }
+}
+
+/// テスト用の作品 (夏目 漱石『坊っちゃん』『吾輩は猫である』、宮沢 賢治『よだかの星』、読み付き)
+#[cfg(test)]
+pub(crate) fn sample_works() -> Vec<Work> {
+    let reading = |work: Work, author: &str, title: &str| Work {
+        author_reading: Some(author.to_string()),
+        title_reading: Some(title.to_string()),
+        ..work
+    };
+    vec![
+        reading(
+            Work {
+                url: Some("https://www.aozora.gr.jp/cards/000148/files/752_14964.html".to_string()),
+                ..sample_work(
+                    "752",
+                    "夏目 漱石",
+                    "148",
+                    "坊っちゃん",
+                    "親譲りの無鉄砲で小供の時から損ばかりして居る。",
+                )
+            },
+            "なつめ そうせき",
+            "ぼっちゃん",
+        ),
+        reading(
+            sample_work(
+                "789",
+                "夏目 漱石",
+                "148",
+                "吾輩は猫である",
+                "吾輩は猫である。",
+            ),
+            "なつめ そうせき",
+            "わがはいはねこである",
+        ),
+        reading(
+            sample_work(
+                "473",
+                "宮沢 賢治",
+                "81",
+                "よだかの星",
+                "よだかは、実にみにくい鳥です。",
+            ),
+            "みやざわ けんじ",
+            "よだかのほし",
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        // xtaskの出力と構造が一致していること
+        assert_eq!(MANIFEST.sha256.len(), 64);
+        let json = to_string(&Metadata::new(1, None)).expect("Failed to serialize to JSON");
+        assert!(json.contains(r#""dataset":{"aozorabunko_commit":"#));
+    }
+
+    #[test]
+    fn test_overlapping_author_names() {
+        // 作者名が部分一致する作者、同名の別人を人物IDで区別する
+        let works: Vec<Work> = serde_json::from_str(
+            r#"[
+                {"id":"1","author":"林 芙美子","author_id":"291","title":"放浪記","text":"","url":null,"ruby":null},
+                {"id":"2","author":"小林 芙美子","author_id":"9001","title":"別人の作品","text":"","url":null,"ruby":null},
+                {"id":"3","author":"林 芙美子","author_id":"9002","title":"同名の別人の作品","text":"","url":null,"ruby":null}
+            ]"#,
+        )
+        .expect("Failed to parse works");
+
+        let authors = works.authors();
+        assert_eq!(
+            authors
+                .iter()
+                .map(|author| (author.id.as_str(), author.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("9001", "小林 芙美子"),
+                ("291", "林 芙美子"),
+                ("9002", "林 芙美子")
+            ]
+        );
+        assert_eq!(works.titles("291"), vec!["放浪記"]);
+
+        let ids = |queries: &[Query]| -> Vec<String> {
+            works
+                .filter(queries)
+                .into_iter()
+                .map(|work| work.id)
+                .collect()
+        };
+        assert_eq!(ids(&[Query::author_id("291")]), vec!["1"]);
+        assert_eq!(ids(&[Query::author("林 芙美子")]), vec!["1", "2", "3"]);
+        assert_eq!(
+            ids(&["author_id=9001".parse().expect("Failed to parse query")]),
+            vec!["2"]
+        );
+    }
 }
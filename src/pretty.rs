@@ -0,0 +1,251 @@
+use clap::ValueEnum;
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::models::Work;
+
+/// 端末幅が取得できない場合の出力幅
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// 作者の色 (太字のシアン)
+const AUTHOR_COLOR: &str = "\x1b[1;36m";
+/// 作品名の色 (黄)
+const TITLE_COLOR: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// 行頭禁則文字
+const NO_START: &str = "、。，．・：；？！‼⁇⁈⁉ー―…‥」』）］｝〕〉》】〙〗”’ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶ々ゝゞヽヾ,.:;!?)]}";
+/// 行末禁則文字
+const NO_END: &str = "「『（［｛〔〈《【〘〖“‘([{";
+
+/// 枠線の種類
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Border {
+    #[default]
+    None,
+    Ascii,
+    Single,
+    Rounded,
+    Double,
+}
+
+impl Border {
+    /// 左上、右上、左下、右下、横線、縦線
+    fn chars(self) -> Option<[char; 6]> {
+        match self {
+            Border::None => None,
+            Border::Ascii => Some(['+', '+', '+', '+', '-', '|']),
+            Border::Single => Some(['┌', '┐', '└', '┘', '─', '│']),
+            Border::Rounded => Some(['╭', '╮', '╰', '╯', '─', '│']),
+            Border::Double => Some(['╔', '╗', '╚', '╝', '═', '║']),
+        }
+    }
+}
+
+/// 整形出力の設定
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub border: Border,
+    pub width: usize,
+    pub color: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            border: Border::None,
+            width: DEFAULT_WIDTH,
+            color: false,
+        }
+    }
+}
+
+/// 整形出力 (禁則処理付きの折り返し、枠線、右寄せの作者と作品名)
+pub fn pretty(work: &Work, style: Style) -> String {
+    let frame = style.border.chars();
+    // 枠線と左右の余白の分を除く
+    let width = if frame.is_some() {
+        style.width.saturating_sub(4)
+    } else {
+        style.width
+    }
+    .max(2);
+
+    let prefix = "— ";
+    let attribution = format!("{prefix}{}『{}』", work.author, work.title);
+    // 作者と作品名の文字位置 (折り返し後の行を位置で着色する)
+    let author_start = prefix.chars().count();
+    let author_end = author_start + work.author.chars().count();
+    let title_start = author_end + 1;
+    let spans = [
+        (author_start..author_end, AUTHOR_COLOR),
+        (
+            title_start..title_start + work.title.chars().count(),
+            TITLE_COLOR,
+        ),
+    ];
+    let mut lines: Vec<(String, usize)> = wrap(&work.text, width)
+        .into_iter()
+        .map(|line| {
+            let line_width = line.width();
+            (line, line_width)
+        })
+        .collect();
+
+    let mut offset = 0;
+    let attribution_lines: Vec<(String, usize)> = wrap(&attribution, width)
+        .into_iter()
+        .map(|line| {
+            let line_width = line.width();
+            let colored = if style.color {
+                colorize(&line, offset, &spans)
+            } else {
+                line.clone()
+            };
+            offset += line.chars().count();
+            (colored, line_width)
+        })
+        .collect();
+
+    // 枠線ありの場合は内容の最大幅に合わせる
+    let inner = lines
+        .iter()
+        .chain(&attribution_lines)
+        .map(|(_, line_width)| *line_width)
+        .max()
+        .unwrap_or(0);
+
+    lines.push((String::new(), 0));
+    lines.extend(attribution_lines.into_iter().map(|(line, line_width)| {
+        // 右寄せ
+        let padding = if frame.is_some() { inner } else { width }.saturating_sub(line_width);
+        (" ".repeat(padding) + &line, padding + line_width)
+    }));
+
+    match frame {
+        None => lines
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Some(
+            [
+                top_left,
+                top_right,
+                bottom_left,
+                bottom_right,
+                horizontal,
+                vertical,
+            ],
+        ) => {
+            let rule = horizontal.to_string().repeat(inner + 2);
+            let mut output = vec![format!("{top_left}{rule}{top_right}")];
+            output.extend(lines.into_iter().map(|(line, line_width)| {
+                let padding = " ".repeat(inner.saturating_sub(line_width));
+                format!("{vertical} {line}{padding} {vertical}")
+            }));
+            output.push(format!("{bottom_left}{rule}{bottom_right}"));
+            output.join("\n")
+        }
+    }
+}
+
+/// 表示幅で折り返す (行頭・行末禁則は追い出しで処理する)
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current: Vec<char> = Vec::new();
+    let mut current_width = 0;
+
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if current_width + char_width > width && !current.is_empty() {
+            let mut carry: Vec<char> = Vec::new();
+            // 行頭禁則: 禁則文字でない文字が見つかるまで前行の末尾を追い出す
+            if NO_START.contains(c) {
+                while let Some(last) = current.pop() {
+                    carry.insert(0, last);
+                    if !NO_START.contains(last) {
+                        break;
+                    }
+                }
+            }
+            // 行末禁則
+            while current.last().is_some_and(|last| NO_END.contains(*last)) {
+                carry.insert(0, current.pop().unwrap_or_default());
+            }
+            // 禁則処理で行が空になる場合はそのまま折り返す
+            if current.is_empty() {
+                current = std::mem::take(&mut carry);
+            }
+
+            lines.push(current.into_iter().collect());
+            current = carry;
+            current_width = current.iter().map(|c| c.width().unwrap_or(0)).sum();
+        }
+        current.push(c);
+        current_width += char_width;
+    }
+
+    if !current.is_empty() {
+        lines.push(current.into_iter().collect());
+    }
+    lines
+}
+
+/// 範囲内の文字を着色する (`offset`は行頭の文字位置、範囲が行をまたぐ場合は行ごとに閉じる)
+fn colorize(line: &str, offset: usize, spans: &[(Range<usize>, &str)]) -> String {
+    let mut output = String::new();
+    let mut current = None;
+    for (i, c) in line.chars().enumerate() {
+        let color = spans
+            .iter()
+            .find(|(range, _)| range.contains(&(offset + i)))
+            .map(|(_, color)| *color);
+        if color != current {
+            if current.is_some() {
+                output.push_str(RESET);
+            }
+            if let Some(color) = color {
+                output.push_str(color);
+            }
+            current = color;
+        }
+        output.push(c);
+    }
+    if current.is_some() {
+        output.push_str(RESET);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_kinsoku() {
+        // 行頭禁則 (`。」`を行頭に置かない)
+        assert_eq!(
+            wrap("「あいうえ。」かき", 10),
+            vec!["「あいう", "え。」かき"]
+        );
+        // 行末禁則 (`「`を行末に置かない)
+        assert_eq!(wrap("あいう「えお」", 8), vec!["あいう", "「えお」"]);
+        assert_eq!(wrap("abcdef", 4), vec!["abcd", "ef"]);
+    }
+
+    #[test]
+    fn test_colorize() {
+        let spans = [(2..4, AUTHOR_COLOR), (5..7, TITLE_COLOR)];
+        // 作品名が作者名に含まれる場合も位置で着色する
+        assert_eq!(
+            colorize("— 賢治『賢治』", 0, &spans),
+            "— \x1b[1;36m賢治\x1b[0m『\x1b[33m賢治\x1b[0m』"
+        );
+        // 折り返した行は行頭の文字位置から着色する
+        assert_eq!(
+            colorize("治『賢治』", 3, &spans),
+            "\x1b[1;36m治\x1b[0m『\x1b[33m賢治\x1b[0m』"
+        );
+    }
+}
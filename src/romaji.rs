@@ -0,0 +1,183 @@
+/// かな (ひらがな・カタカナ) をヘボン式ローマ字に変換する
+///
+/// かな以外の文字はそのまま残す
+pub fn to_romaji(kana: &str) -> String {
+    let chars: Vec<char> = kana.chars().map(to_hiragana).collect();
+    let mut output = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        // 拗音 (きゃ など)
+        if let Some(romaji) = chars
+            .get(i..i + 2)
+            .and_then(|pair| youon(&pair.iter().collect::<String>()))
+        {
+            output.push_str(romaji);
+            i += 2;
+            continue;
+        }
+
+        match chars[i] {
+            // 促音: 次の子音を重ねる
+            'っ' => {
+                let next = chars
+                    .get(i + 1..)
+                    .map(|rest| to_romaji(&rest.iter().take(2).collect::<String>()))
+                    .unwrap_or_default();
+                match next.chars().next() {
+                    Some('c') => output.push('t'),
+                    Some(c) if c.is_ascii_alphabetic() && !"aiueon".contains(c) => output.push(c),
+                    _ => output.push_str("tsu"),
+                }
+            }
+            // 長音: 直前の母音を重ねる
+            'ー' => {
+                if let Some(vowel) = output.chars().last().filter(|c| "aiueo".contains(*c)) {
+                    output.push(vowel);
+                }
+            }
+            c => match kana_romaji(c) {
+                Some(romaji) => output.push_str(romaji),
+                None => output.push(c),
+            },
+        }
+        i += 1;
+    }
+
+    output
+}
+
+fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn youon(pair: &str) -> Option<&'static str> {
+    Some(match pair {
+        "きゃ" => "kya",
+        "きゅ" => "kyu",
+        "きょ" => "kyo",
+        "しゃ" => "sha",
+        "しゅ" => "shu",
+        "しょ" => "sho",
+        "ちゃ" => "cha",
+        "ちゅ" => "chu",
+        "ちょ" => "cho",
+        "にゃ" => "nya",
+        "にゅ" => "nyu",
+        "にょ" => "nyo",
+        "ひゃ" => "hya",
+        "ひゅ" => "hyu",
+        "ひょ" => "hyo",
+        "みゃ" => "mya",
+        "みゅ" => "myu",
+        "みょ" => "myo",
+        "りゃ" => "rya",
+        "りゅ" => "ryu",
+        "りょ" => "ryo",
+        "ぎゃ" => "gya",
+        "ぎゅ" => "gyu",
+        "ぎょ" => "gyo",
+        "じゃ" | "ぢゃ" => "ja",
+        "じゅ" | "ぢゅ" => "ju",
+        "じょ" | "ぢょ" => "jo",
+        "びゃ" => "bya",
+        "びゅ" => "byu",
+        "びょ" => "byo",
+        "ぴゃ" => "pya",
+        "ぴゅ" => "pyu",
+        "ぴょ" => "pyo",
+        _ => return None,
+    })
+}
+
+fn kana_romaji(c: char) -> Option<&'static str> {
+    Some(match c {
+        'あ' | 'ぁ' => "a",
+        'い' | 'ぃ' | 'ゐ' => "i",
+        'う' | 'ぅ' => "u",
+        'え' | 'ぇ' | 'ゑ' => "e",
+        'お' | 'ぉ' | 'を' => "o",
+        'か' | 'ゕ' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' | 'ゖ' => "ke",
+        'こ' => "ko",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' | 'ゃ' => "ya",
+        'ゆ' | 'ゅ' => "yu",
+        'よ' | 'ょ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' | 'ゎ' => "wa",
+        'ん' => "n",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'ざ' => "za",
+        'じ' | 'ぢ' => "ji",
+        'ず' | 'づ' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'だ' => "da",
+        'で' => "de",
+        'ど' => "do",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ゔ' => "vu",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_romaji() {
+        assert_eq!(to_romaji("なつめ そうせき"), "natsume souseki");
+        assert_eq!(to_romaji("ぼっちゃん"), "botchan");
+        assert_eq!(to_romaji("ちゅうや"), "chuuya");
+        assert_eq!(to_romaji("サッカー"), "sakkaa");
+        assert_eq!(to_romaji("芥川"), "芥川");
+    }
+}
@@ -0,0 +1,250 @@
+use clap::ValueEnum;
+use percent_encoding::percent_decode_str;
+use serde_json::{json, to_string};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::index::Index;
+use crate::models::{JsonShape, Metadata, PrintOptions, Query, QueryKey, Work, Works};
+
+/// ローカルHTTPサーバを起動する
+///
+/// - `GET /random?author=…&title=…&text=…&n=…&seed=…&shape=…`
+/// - `GET /works/{id}` (共著の作品は作者ごとの配列)
+/// - `GET /authors`
+/// - `GET /authors/{id}/titles` (`id`は人物ID)
+/// - `GET /daily` (`/random`と同じフィルタを指定可)
+pub fn serve(works: &Vec<Work>, host: IpAddr, port: u16) -> io::Result<()> {
+    let server = Server::http((host, port)).map_err(io::Error::other)?;
+    let index = Index::new(works);
+    eprintln!("Listening on http://{}", server.server_addr());
+
+    for request in server.incoming_requests() {
+        respond(works, &index, request);
+    }
+    Ok(())
+}
+
+/// 待ち受けるアドレス (ローカルからのみ接続できるようループバックアドレスに限る)
+pub fn parse_host(host: &str) -> Result<IpAddr, String> {
+    let address = if host == "localhost" {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    } else {
+        host.parse().map_err(|e| format!("{e}"))?
+    };
+    if address.is_loopback() {
+        Ok(address)
+    } else {
+        Err(format!("{address} is not a loopback address"))
+    }
+}
+
+fn respond(works: &Vec<Work>, index: &Index, request: Request) {
+    let (status, body) = if *request.method() == Method::Get {
+        handle(works, index, request.url())
+    } else {
+        error(405, "Method not allowed")
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8")
+        .expect("Invalid header");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        eprintln!("Failed to respond: {e}");
+    }
+}
+
+/// リクエストURLからステータスコードとJSONレスポンスを返す
+fn handle(works: &Vec<Work>, index: &Index, url: &str) -> (u16, String) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let params: Vec<(String, String)> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect();
+
+    match segments.as_slice() {
+        ["random"] => random(works, index, &params, None),
+        ["daily"] => random(works, index, &params, Some(today())),
+        // 共著の作品は作者ごとにあるため、一致するものをすべて返す
+        ["works", id] => match works
+            .iter()
+            .filter(|work| work.id == *id)
+            .cloned()
+            .collect::<Vec<Work>>()
+        {
+            found if found.is_empty() => error(404, &format!("Work not found: {id}")),
+            found => (200, found.to_json(&PrintOptions::default())),
+        },
+        ["authors"] => (200, json(&works.authors())),
+        ["authors", author_id, "titles"] => match works.titles(author_id) {
+            titles if titles.is_empty() => error(404, &format!("Author not found: {author_id}")),
+            titles => (200, json(&titles)),
+        },
+        _ => error(404, "Not found"),
+    }
+}
+
+/// `/random`と`/daily`のレスポンス (`/daily`は日付をシードとし1件のみ返す)
+fn random(
+    works: &[Work],
+    index: &Index,
+    params: &[(String, String)],
+    daily: Option<u64>,
+) -> (u16, String) {
+    let mut queries = Vec::new();
+    let mut number = 1;
+    let mut seed = None;
+    let mut json_shape = JsonShape::Auto;
+
+    for (key, value) in params {
+        let parsed = match key.as_str() {
+            "n" if daily.is_none() => value.parse().map(|n| number = n).is_ok(),
+            "seed" if daily.is_none() => value.parse().map(|s| seed = Some(s)).is_ok(),
+            "shape" => JsonShape::from_str(value, true)
+                .map(|shape| json_shape = shape)
+                .is_ok(),
+            key => QueryKey::from_str(key, true)
+                .map(|key| {
+                    queries.push(Query {
+                        key,
+                        value: value.clone(),
+                    });
+                })
+                .is_ok(),
+        };
+        if !parsed {
+            return error(400, &format!("Invalid parameter: {key}={value}"));
+        }
+    }
+
+    let seed = daily.or(seed).unwrap_or_else(rand::random);
+    let matched = index.filter(works, &queries);
+    let json = matched
+        .random(Some(seed))
+        .take(number)
+        .to_json(&PrintOptions {
+            json_shape,
+            metadata: Metadata::new(matched.len(), Some(seed)),
+            ..PrintOptions::default()
+        });
+    (200, json)
+}
+
+/// UNIXエポックからの日数 (UTC)
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86400)
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(&s.replace('+', " "))
+        .decode_utf8_lossy()
+        .to_string()
+}
+
+fn json<T: serde::Serialize>(value: &T) -> String {
+    to_string(value).expect("Failed to serialize to JSON")
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "error": message }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::sample_works;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+
+    #[test]
+    fn test_handle() {
+        let works = sample_works();
+        let index = Index::new(&works);
+        let handle = |url: &str| handle(&works, &index, url);
+
+        let (status, body) = handle("/works/789");
+        assert_eq!(status, 200);
+        assert!(body.starts_with(r#"{"id":"789","author":"夏目 漱石""#));
+
+        assert_eq!(handle("/works/1").0, 404);
+
+        // 共著の作品は作者ごとに返す
+        let mut coauthored = works.clone();
+        let mut coauthor = works[1].clone();
+        coauthor.author = "共著 者".to_string();
+        coauthor.author_id = "1".to_string();
+        coauthored.push(coauthor);
+        let (status, body) = super::handle(&coauthored, &index, "/works/789");
+        assert_eq!(status, 200);
+        assert!(body.starts_with(r#"[{"id":"789","author":"夏目 漱石""#));
+        assert!(body.contains(r#"{"id":"789","author":"共著 者""#));
+        assert_eq!(
+            handle("/authors"),
+            (
+                200,
+                r#"[{"id":"148","name":"夏目 漱石"},{"id":"81","name":"宮沢 賢治"}]"#.to_string()
+            )
+        );
+        assert_eq!(
+            handle("/authors/81/titles"),
+            (200, r#"["よだかの星"]"#.to_string())
+        );
+        assert_eq!(handle("/authors/8/titles").0, 404);
+
+        let (status, body) = handle("/random?author=%E5%A4%8F%E7%9B%AE&n=5&shape=array");
+        assert_eq!(status, 200);
+        assert_eq!(body.matches(r#""author":"夏目 漱石""#).count(), 2);
+
+        assert_eq!(handle("/random?n=x").0, 400);
+        assert_eq!(handle("/daily").1, handle("/daily").1);
+        assert_eq!(handle("/unknown").0, 404);
+    }
+
+    #[test]
+    fn test_parse_host() {
+        assert_eq!(parse_host("localhost"), Ok(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert!(parse_host("::1").is_ok());
+        assert!(parse_host("0.0.0.0").is_err());
+        assert!(parse_host("192.168.0.1").is_err());
+    }
+
+    #[test]
+    fn test_serve_local() {
+        let server = Server::http("127.0.0.1:0").expect("Failed to bind");
+        let address = server.server_addr().to_ip().expect("Failed to get address");
+        let handle = thread::spawn(move || {
+            let request = server.recv().expect("Failed to receive request");
+            let works = sample_works();
+            respond(&works, &Index::new(&works), request);
+        });
+
+        let mut stream = TcpStream::connect(address).expect("Failed to connect");
+        stream
+            .write_all(b"GET /works/752 HTTP/1.0\r\n\r\n")
+            .expect("Failed to send request");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Failed to read response");
+        handle.join().expect("Server thread panicked");
+
+        assert!(response.starts_with("HTTP/1.0 200"));
+        assert!(response.contains("application/json"));
+        assert!(response.ends_with(r#""title":"坊っちゃん","text":"親譲りの無鉄砲で小供の時から損ばかりして居る。","url":"https://www.aozora.gr.jp/cards/000148/files/752_14964.html"}"#));
+    }
+}
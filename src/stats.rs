@@ -0,0 +1,242 @@
+use serde::Serialize;
+use serde_json::to_string;
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+
+use crate::listing::{self, ListFormat, Sort};
+use crate::models::{Field, Work};
+
+/// 頻度表に出力する件数
+const TOP: usize = 10;
+
+/// 表形式の見出しの表示幅
+const LABEL_WIDTH: usize = 18;
+
+/// 文末とみなす文字
+const SENTENCE_END: [char; 6] = ['。', '！', '？', '!', '?', '．'];
+
+/// コーパスの統計
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub works: usize,
+    pub authors: usize,
+    pub works_per_author: Distribution,
+    pub top_authors: Vec<(String, usize)>,
+    pub chars: Distribution,
+    pub sentences: Distribution,
+    /// `「`で始まる書き出しの割合
+    pub starts_with_bracket: f64,
+    /// 疑問符で終わる書き出しの割合
+    pub ends_with_question: f64,
+    /// URLのない作品の割合
+    pub without_url: f64,
+    pub first_chars: Vec<(String, usize)>,
+    pub first_words: Vec<(String, usize)>,
+}
+
+/// 分布の要約
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Distribution {
+    pub min: usize,
+    pub median: usize,
+    pub mean: f64,
+    pub p90: usize,
+    pub max: usize,
+}
+
+impl Distribution {
+    fn new(mut values: Vec<usize>) -> Self {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+        values.sort_unstable();
+        let percentile = |p: usize| values[(values.len() - 1) * p / 100];
+        Distribution {
+            min: values[0],
+            median: percentile(50),
+            mean: ratio(values.iter().sum(), values.len()),
+            p90: percentile(90),
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+impl Stats {
+    pub fn new(works: &[Work]) -> Self {
+        let authors = listing::authors(works, Sort::Count);
+        let share = |predicate: &dyn Fn(&Work) -> bool| {
+            ratio(
+                works.iter().filter(|work| predicate(work)).count(),
+                works.len(),
+            )
+        };
+
+        Stats {
+            works: works.len(),
+            authors: authors.len(),
+            works_per_author: Distribution::new(authors.iter().map(|entry| entry.count).collect()),
+            top_authors: authors
+                .iter()
+                .take(TOP)
+                .map(|entry| (entry.name.clone(), entry.count))
+                .collect(),
+            chars: Distribution::new(works.iter().map(|work| work.text.chars().count()).collect()),
+            sentences: Distribution::new(works.iter().map(|work| sentences(&work.text)).collect()),
+            starts_with_bracket: share(&|work| work.text.starts_with('「')),
+            ends_with_question: share(&|work| {
+                work.text
+                    .trim_end_matches(['」', '』', '。'])
+                    .ends_with(['？', '?'])
+            }),
+            without_url: share(&|work| Field::Url.value(work).is_empty()),
+            first_chars: frequent(works.iter().filter_map(|work| {
+                work.text
+                    .chars()
+                    .find(|c| !c.is_whitespace())
+                    .map(String::from)
+            })),
+            first_words: frequent(works.iter().filter_map(|work| first_word(&work.text))),
+        }
+    }
+
+    pub fn print(&self, format: ListFormat) {
+        match format {
+            ListFormat::Json => {
+                println!("{}", to_string(self).expect("Failed to serialize to JSON"));
+            }
+            ListFormat::Plain => {
+                let percent = |share: f64| format!("{:.1}%", share * 100.0);
+                let rows = [
+                    ("works", self.works.to_string()),
+                    ("authors", self.authors.to_string()),
+                    ("works per author", self.works_per_author.to_string()),
+                    ("opening chars", self.chars.to_string()),
+                    ("opening sentences", self.sentences.to_string()),
+                    ("starts with 「", percent(self.starts_with_bracket)),
+                    ("ends with ?", percent(self.ends_with_question)),
+                    ("without url", percent(self.without_url)),
+                    ("top authors", table(&self.top_authors)),
+                    ("first chars", table(&self.first_chars)),
+                    ("first words", table(&self.first_words)),
+                ];
+                for (label, value) in rows {
+                    let padding = " ".repeat(LABEL_WIDTH.saturating_sub(label.width()));
+                    println!("{label}{padding} {value}");
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {} / median {} / mean {:.1} / p90 {} / max {}",
+            self.min, self.median, self.mean, self.p90, self.max
+        )
+    }
+}
+
+/// 割合 (分母が0の場合は0)
+#[allow(clippy::cast_precision_loss)] // 作品数・文字数の範囲では精度は問題にならない
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// 文の数 (文末記号で区切る。末尾に文末記号がない場合も1文と数える)
+fn sentences(text: &str) -> usize {
+    text.split(SENTENCE_END)
+        .filter(|sentence| !sentence.trim_start_matches('」').trim().is_empty())
+        .count()
+}
+
+/// 書き出しの最初の語
+///
+/// 日本語は分かち書きされないため、先頭から同じ字種 (漢字、ひらがな、カタカナ、英数字) が
+/// 続く範囲を1語とみなす (括弧などの記号は読み飛ばす)
+fn first_word(text: &str) -> Option<String> {
+    let mut chars = text.chars().skip_while(|c| script(*c).is_none()).peekable();
+    let kind = script(*chars.peek()?);
+    Some(chars.take_while(|c| script(*c) == kind).collect())
+}
+
+#[derive(PartialEq)]
+enum Script {
+    Kanji,
+    Hiragana,
+    Katakana,
+    Alphanumeric,
+}
+
+fn script(c: char) -> Option<Script> {
+    match c {
+        '々' | '〆' | '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => Some(Script::Kanji),
+        '\u{3041}'..='\u{309F}' => Some(Script::Hiragana),
+        'ー' | '\u{30A1}'..='\u{30FA}' => Some(Script::Katakana),
+        c if c.is_alphanumeric() => Some(Script::Alphanumeric),
+        _ => None,
+    }
+}
+
+/// 頻度の高い順に上位を返す (同数の場合は値の順)
+fn frequent(values: impl Iterator<Item = String>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts.truncate(TOP);
+    counts
+}
+
+fn table(counts: &[(String, usize)]) -> String {
+    counts
+        .iter()
+        .map(|(value, count)| format!("{value} ({count})"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{sample_work, sample_works};
+
+    #[test]
+    fn test_stats() {
+        let mut works = sample_works()[..2].to_vec();
+        works[1].text = "吾輩は猫である。名前はまだ無い。".to_string();
+        works.push(sample_work("1", "作者", "1", "作品", "「誰だ？」"));
+        let stats = Stats::new(&works);
+
+        assert_eq!((stats.works, stats.authors), (3, 2));
+        assert_eq!(stats.top_authors[0], ("夏目 漱石".to_string(), 2));
+        assert_eq!(
+            stats.sentences,
+            Distribution {
+                min: 1,
+                median: 1,
+                mean: 4.0 / 3.0,
+                p90: 1,
+                max: 2,
+            }
+        );
+        assert!((stats.starts_with_bracket - 1.0 / 3.0).abs() < f64::EPSILON);
+        assert!((stats.ends_with_question - 1.0 / 3.0).abs() < f64::EPSILON);
+        assert!((stats.without_url - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(
+            stats.first_words,
+            vec![
+                ("吾輩".to_string(), 1),
+                ("親譲".to_string(), 1),
+                ("誰".to_string(), 1)
+            ]
+        );
+    }
+}
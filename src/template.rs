@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use crate::format;
+use crate::models::{Field, Work};
+
+/// 出典 (底本・初出・入力・校正を含む) のプレースホルダ
+///
+/// 作品データの項目ではないため`csv`/`json`形式の列/キーにはならない
+const CITATION: &str = "{citation}";
+
+/// `quote`形式のデフォルトテンプレート
+pub const DEFAULT_TEMPLATE: &str = "{text}｜{author}『{title}』";
+
+/// 組み込みの名前付きテンプレート
+///
+/// 設定ディレクトリ (`$XDG_CONFIG_HOME/kakidashi/templates/{name}.tmpl`) に
+/// 同名のファイルがあればそちらを優先する
+const PRESETS: [(&str, &str); 4] = [
+    ("default", DEFAULT_TEMPLATE),
+    ("dash", "{text} - {author} ({title})"),
+    ("slack", "> {text}\\n> — {author}『<{url}|{title}>』"),
+    ("url", "{text}\\n{url}"),
+];
+
+/// テンプレートを検証する (プレースホルダを1つ以上含むこと)
+pub fn validate(template: &str) -> Result<String, String> {
+    if fields(template).is_empty() && !template.contains(CITATION) {
+        Err(format!(
+            "Template must contain at least one of the placeholders: {}",
+            placeholder_list()
+        ))
+    } else {
+        Ok(template.to_string())
+    }
+}
+
+/// `csv`/`tsv`/`json`/`jsonl`形式のテンプレートを検証する (`{citation}`は列/キーにならない)
+pub fn validate_columns(template: &str) -> Result<(), String> {
+    if template.contains(CITATION) {
+        Err(format!(
+            "{CITATION} cannot be used with --format csv, tsv, json or jsonl. Use --fields base_text,first_published,inputter,proofreader instead"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// テンプレートファイルを読み込む
+pub fn read_file(path: &Path) -> Result<String, String> {
+    let template = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read template file {}: {e}", path.display()))?;
+    validate(template.trim_end_matches(['\r', '\n']))
+}
+
+/// 名前付きテンプレートを取得する
+///
+/// 名前は設定ディレクトリのファイル名になるため、パス区切りと`..`を含む名前は拒否する
+pub fn preset(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!(
+            "Invalid template name '{name}'. Names cannot be empty or contain path separators or '..'"
+        ));
+    }
+    if let Some(path) = config_dir().map(|dir| dir.join(format!("{name}.tmpl")))
+        && path.is_file()
+    {
+        return read_file(&path);
+    }
+
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, template)| (*template).to_string())
+        .ok_or_else(|| {
+            let names: Vec<&str> = PRESETS.iter().map(|(preset, _)| *preset).collect();
+            format!(
+                "Unknown template name '{name}'. Built-in templates: {}",
+                names.join(", ")
+            )
+        })
+}
+
+/// テンプレートに作品データを埋め込む
+///
+/// テンプレートを先頭から1回だけ走査するため、埋め込んだ値に含まれる`{text}`などは置換しない
+pub fn render(template: &str, work: &Work) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '\\']) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("\\n") {
+            output.push('\n');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(CITATION) {
+            output.push_str(&format::citation(work));
+            rest = after;
+        } else if let Some((field, after)) = Field::ALL.iter().find_map(|field| {
+            rest.strip_prefix(field.placeholder().as_str())
+                .map(|after| (field, after))
+        }) {
+            output.push_str(field.value(work));
+            rest = after;
+        } else {
+            // `{`と`\`は1バイト
+            output.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// テンプレートに含まれるプレースホルダを出現順に抽出する
+///
+/// `csv`/`json`形式ではこれを列/キーとして扱う
+pub fn fields(template: &str) -> Vec<Field> {
+    let mut positions: Vec<(usize, Field)> = Field::ALL
+        .iter()
+        .filter_map(|field| {
+            template
+                .find(&field.placeholder())
+                .map(|position| (position, *field))
+        })
+        .collect();
+    positions.sort_by_key(|(position, _)| *position);
+    positions.into_iter().map(|(_, field)| field).collect()
+}
+
+fn placeholder_list() -> String {
+    Field::ALL
+        .iter()
+        .map(|field| field.placeholder())
+        .chain([CITATION.to_string()])
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("kakidashi").join("templates"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_columns() {
+        assert!(validate("{citation}").is_ok());
+        assert!(validate_columns("{author}{text}").is_ok());
+        assert!(validate_columns("{text} {citation}").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let work: Work = serde_json::from_str(
+            r#"{"id":"1","author":"{title}","author_id":"1","title":"題{text}","text":"本文。","url":null,"ruby":null}"#,
+        )
+        .expect("Failed to parse work");
+        // 埋め込んだ値のプレースホルダは置換しない
+        assert_eq!(
+            render("{text}\\n{author}『{title}』{unknown}\\t", &work),
+            "本文。\n{title}『題{text}』{unknown}\\t"
+        );
+        assert_eq!(render("{citation}", &work), "{title}『題{text}』青空文庫");
+    }
+
+    #[test]
+    fn test_preset() {
+        assert!(preset("dash").is_ok());
+        for name in ["../../x", "a/b", "a\\b", "..", ""] {
+            assert!(
+                preset(name)
+                    .expect_err("Invalid name must be rejected")
+                    .starts_with("Invalid template name")
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{Ok, Result};
+use csv::StringRecord;
+use flate2::{Compression, read::MultiGzDecoder, write::GzEncoder};
+use std::fs::{File, create_dir_all};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::models::WorkRecord;
+
+/// データセットの列数
+pub const COLUMNS: usize = 15;
+
+/// 全作品のCSVを書き出す
+pub fn write_csv(records: &[WorkRecord], output_path: &Path) -> Result<()> {
+    if let Some(parent) = output_path.parent() {
+        create_dir_all(parent)?;
+    }
+    write_rows(records, File::create(output_path)?)?;
+    Ok(())
+}
+
+/// 埋め込み用の圧縮CSVを書き出す
+pub fn compress_csv(records: &[WorkRecord], output_path: &Path) -> Result<()> {
+    if let Some(parent) = output_path.parent() {
+        create_dir_all(parent)?;
+    }
+    let encoder = GzEncoder::new(File::create(output_path)?, Compression::default());
+    write_rows(records, encoder)?.finish()?;
+    Ok(())
+}
+
+/// ヘッダなしのCSVとして書き出す (カンマ・改行・引用符を含む項目は引用符で囲む)
+pub fn write_rows<W: Write>(records: &[WorkRecord], output: W) -> Result<W> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(output);
+    for record in records {
+        writer.write_record(to_row(record))?;
+    }
+    Ok(writer.into_inner().map_err(|e| e.into_error())?)
+}
+
+/// CSVまたは圧縮CSV (拡張子`.gz`) を読み込む
+pub fn read_records(input_path: &Path) -> Result<Vec<WorkRecord>> {
+    Ok(read_rows(input_path)?.iter().map(from_row).collect())
+}
+
+/// CSVまたは圧縮CSV (拡張子`.gz`) の各行 (列数は検証しない)
+pub fn read_rows(input_path: &Path) -> Result<Vec<StringRecord>> {
+    let file = File::open(input_path)?;
+    let input: Box<dyn Read> = if input_path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    parse_rows(input)
+}
+
+pub fn parse_rows<R: Read>(input: R) -> Result<Vec<StringRecord>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input);
+    Ok(reader.records().collect::<Result<Vec<_>, _>>()?)
+}
+
+fn to_row(record: &WorkRecord) -> [&str; COLUMNS] {
+    [
+        &record.id,
+        &record.author,
+        &record.author_id,
+        &record.title,
+        &record.text,
+        record.url.as_deref().unwrap_or_default(),
+        record.ruby.as_deref().unwrap_or_default(),
+        record.author_reading.as_deref().unwrap_or_default(),
+        record.title_reading.as_deref().unwrap_or_default(),
+        record.card_url.as_deref().unwrap_or_default(),
+        record.source.as_deref().unwrap_or_default(),
+        record.base_text.as_deref().unwrap_or_default(),
+        record.first_published.as_deref().unwrap_or_default(),
+        record.inputter.as_deref().unwrap_or_default(),
+        record.proofreader.as_deref().unwrap_or_default(),
+    ]
+}
+
+/// 1行を作品に変換する (空の任意項目はNone)
+pub fn from_row(row: &StringRecord) -> WorkRecord {
+    let field = |i: usize| row.get(i).unwrap_or_default().to_string();
+    let optional = |i: usize| Some(field(i)).filter(|s| !s.is_empty());
+    WorkRecord {
+        id: field(0),
+        author: field(1),
+        author_id: field(2),
+        title: field(3),
+        text: field(4),
+        url: optional(5),
+        ruby: optional(6),
+        author_reading: optional(7),
+        title_reading: optional(8),
+        card_url: optional(9),
+        source: optional(10),
+        base_text: optional(11),
+        first_published: optional(12),
+        inputter: optional(13),
+        proofreader: optional(14),
+    }
+}
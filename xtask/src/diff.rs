@@ -0,0 +1,261 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthChar;
+
+use crate::models::WorkRecord;
+
+/// 差分の出力形式
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum DiffFormat {
+    /// 変更前と変更後を左右に並べる
+    #[default]
+    SideBySide,
+    Json,
+}
+
+/// 2つのデータセットの差分
+#[derive(Debug, Default, Serialize)]
+pub struct Diff<'a> {
+    pub summary: Summary,
+    pub added: Vec<&'a WorkRecord>,
+    pub removed: Vec<&'a WorkRecord>,
+    /// 書き出しが変わった作品
+    pub changed: Vec<Change<'a>>,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct Summary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Change<'a> {
+    pub old: &'a WorkRecord,
+    pub new: &'a WorkRecord,
+}
+
+/// 作品IDで照合して差分を求める
+///
+/// 共著・翻訳の作品は複数の作者のページに載るため、作品IDと人物IDの組で照合する。
+/// 照合できなかった作品は作者と作品名の組で照合する (同じ組が複数ある場合は照合しない)
+pub fn diff<'a>(old: &'a [WorkRecord], new: &'a [WorkRecord]) -> Diff<'a> {
+    let (pairs, old_rest, new_rest) = pair(
+        old.iter().collect(),
+        new.iter().collect(),
+        |record: &WorkRecord| (record.id.clone(), record.author_id.clone()),
+    );
+    let (fallback_pairs, removed, added) = pair(old_rest, new_rest, |record: &WorkRecord| {
+        (record.author.clone(), record.title.clone())
+    });
+
+    let (unchanged, changed): (Vec<_>, Vec<_>) = pairs
+        .into_iter()
+        .chain(fallback_pairs)
+        .partition(|(old, new)| old.text == new.text);
+    let mut changed: Vec<Change<'a>> = changed
+        .into_iter()
+        .map(|(old, new)| Change { old, new })
+        .collect();
+    changed.sort_by_key(|change| (&change.new.author, &change.new.title, &change.new.id));
+
+    Diff {
+        summary: Summary {
+            added: added.len(),
+            removed: removed.len(),
+            changed: changed.len(),
+            unchanged: unchanged.len(),
+        },
+        added,
+        removed,
+        changed,
+    }
+}
+
+/// キーが一意な作品同士を組にし、(組, 残りの変更前, 残りの変更後) を返す
+fn pair<'a, K: Eq + std::hash::Hash>(
+    old: Vec<&'a WorkRecord>,
+    new: Vec<&'a WorkRecord>,
+    key: impl Fn(&WorkRecord) -> K,
+) -> (
+    Vec<(&'a WorkRecord, &'a WorkRecord)>,
+    Vec<&'a WorkRecord>,
+    Vec<&'a WorkRecord>,
+) {
+    let mut counts: HashMap<K, (usize, usize)> = HashMap::new();
+    for record in &old {
+        counts.entry(key(record)).or_default().0 += 1;
+    }
+    for record in &new {
+        counts.entry(key(record)).or_default().1 += 1;
+    }
+    let unique = |record: &WorkRecord| counts.get(&key(record)) == Some(&(1, 1));
+
+    let mut new_unique: HashMap<K, &WorkRecord> = HashMap::new();
+    let mut new_rest = Vec::new();
+    for record in new {
+        if unique(record) {
+            new_unique.insert(key(record), record);
+        } else {
+            new_rest.push(record);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut old_rest = Vec::new();
+    for record in old {
+        match new_unique.remove(&key(record)) {
+            Some(new_record) => pairs.push((record, new_record)),
+            None => old_rest.push(record),
+        }
+    }
+    // 変更前に対応する作品がなかった一意な作品
+    new_rest.extend(new_unique.into_values());
+    new_rest.sort_by_key(|record| (&record.author, &record.title, &record.id));
+
+    (pairs, old_rest, new_rest)
+}
+
+/// 差分を出力する
+///
+/// `side-by-side`形式では`+`: 追加、`-`: 削除、`~`: 変更の見出しに続けて、
+/// 変更前 (左) と変更後 (右) の書き出しを`width`の表示幅に収めて並べる
+pub fn print(diff: &Diff<'_>, format: DiffFormat, width: usize) {
+    match format {
+        DiffFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string(diff).expect("Failed to serialize to JSON")
+            );
+        }
+        DiffFormat::SideBySide => {
+            let column = width.saturating_sub(3) / 2;
+            for record in &diff.added {
+                println!("+ {}", label(record));
+                print_columns("", &record.text, column);
+            }
+            for record in &diff.removed {
+                println!("- {}", label(record));
+                print_columns(&record.text, "", column);
+            }
+            for change in &diff.changed {
+                println!("~ {}", label(change.new));
+                print_columns(&change.old.text, &change.new.text, column);
+            }
+            let summary = &diff.summary;
+            println!(
+                "Added: {}, Removed: {}, Changed: {}, Unchanged: {}",
+                summary.added, summary.removed, summary.changed, summary.unchanged
+            );
+        }
+    }
+}
+
+fn label(record: &WorkRecord) -> String {
+    format!("{} {}『{}』", record.id, record.author, record.title)
+}
+
+fn print_columns(left: &str, right: &str, width: usize) {
+    let left = wrap(left, width);
+    let right = wrap(right, width);
+    for i in 0..left.len().max(right.len()) {
+        let (left, left_width) = left.get(i).cloned().unwrap_or_default();
+        let (right, _) = right.get(i).cloned().unwrap_or_default();
+        let padding = " ".repeat(width.saturating_sub(left_width));
+        println!("  {left}{padding} │ {right}");
+    }
+}
+
+/// 表示幅`width`ごとに折り返した行とその表示幅
+fn wrap(text: &str, width: usize) -> Vec<(String, usize)> {
+    let mut lines: Vec<(String, usize)> = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if line_width + char_width > width && !line.is_empty() {
+            lines.push((std::mem::take(&mut line), line_width));
+            line_width = 0;
+        }
+        line.push(c);
+        line_width += char_width;
+    }
+    if !line.is_empty() {
+        lines.push((line, line_width));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, author_id: &str, title: &str, text: &str) -> WorkRecord {
+        WorkRecord {
+            id: id.to_string(),
+            author: "著者".to_string(),
+            author_id: author_id.to_string(),
+            title: title.to_string(),
+            text: text.to_string(),
+            ..WorkRecord::default()
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = [
+            record("1", "1", "甲", "あ。"),
+            record("2", "1", "乙", "い。"),
+            record("2", "2", "乙", "い。"),
+            record("4", "1", "丁", "お。"),
+        ];
+        let new = [
+            record("1", "1", "甲", "あ。"),
+            record("2", "1", "乙", "う。"),
+            record("3", "1", "丙", "え。"),
+            // 作品IDが変わった作品は作者と作品名で照合する
+            record("40", "1", "丁", "か。"),
+        ];
+        let diff = diff(&old, &new);
+
+        let ids = |records: &[&WorkRecord]| -> Vec<(String, String)> {
+            records
+                .iter()
+                .map(|r| (r.id.clone(), r.author_id.clone()))
+                .collect()
+        };
+        assert_eq!(
+            diff.summary,
+            Summary {
+                added: 1,
+                removed: 1,
+                changed: 2,
+                unchanged: 1,
+            }
+        );
+        assert_eq!(ids(&diff.added), vec![("3".to_string(), "1".to_string())]);
+        assert_eq!(ids(&diff.removed), vec![("2".to_string(), "2".to_string())]);
+        assert_eq!(
+            diff.changed
+                .iter()
+                .map(|change| (change.old.id.as_str(), change.new.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("4", "か。"), ("2", "う。")]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("吾輩は猫。ab", 4),
+            vec![
+                ("吾輩".to_string(), 4),
+                ("は猫".to_string(), 4),
+                ("。ab".to_string(), 4)
+            ]
+        );
+    }
+}
@@ -0,0 +1,459 @@
+//! 青空文庫形式のテキストの構造と本文のレイアウト注記の解釈
+//!
+//! テキストファイルは次の構造を持つ
+//!
+//! 1. ヘッダ: 作品名、著者名など (最初の空行まで)
+//! 2. 記号の説明: `-----`の行で囲まれた`【テキスト中に現れる記号について】`
+//! 3. 本文: `底本：`または`［＃本文終わり］`の行まで
+//! 4. 後付け: `底本：`以降 (底本、初出、入力・校正など)
+
+use crate::models::Citation;
+
+/// 青空文庫形式のテキスト
+#[derive(Debug, Default, PartialEq)]
+pub struct Document<'a> {
+    pub header: Vec<&'a str>,
+    pub body: Vec<Line>,
+    pub colophon: Vec<&'a str>,
+}
+
+/// 本文の1行 (レイアウト注記を除いたテキストと、注記が表す行の種類)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: String,
+    pub kind: LineKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    /// 本文の段落
+    Text,
+    /// 見出し (`［＃「一」は大見出し］`、`［＃ここから中見出し］`など)
+    Heading,
+    /// 字下げ・地付き・字上げされた行 (引用、詩、題辞、手紙など)
+    Indented,
+    /// 空行、または注記のみの行 (`［＃改ページ］`など)
+    Blank,
+}
+
+/// レイアウト注記 (本文から除き、行の種類として解釈する)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Layout {
+    /// `［＃「一」は大見出し］`、`［＃中見出し］`、`［＃ここから小見出し］`など
+    Heading,
+    /// `［＃３字下げ］`、`［＃ここから２字下げ］`、`［＃地付き］`、`［＃地から２字上げ］`など
+    Indent,
+    /// `［＃改ページ］`、`［＃本文終わり］`など
+    Break,
+}
+
+/// 注記が及ぶ範囲
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    /// その行のみ
+    Line,
+    /// `ここから…`: 次の`ここで…終わり`まで
+    Start,
+    /// `ここで…終わり`、`…終わり`
+    End,
+}
+
+/// テキストファイルの行をヘッダ、本文、後付けに分ける
+pub fn parse<'a>(lines: &[&'a str]) -> Document<'a> {
+    let header_end = lines
+        .iter()
+        .position(|line| line.trim().is_empty())
+        .unwrap_or(lines.len());
+    let mut rest = lines[header_end..]
+        .iter()
+        .skip_while(|line| line.trim().is_empty())
+        .copied()
+        .peekable();
+
+    // 記号の説明
+    if rest.next_if(|line| line.starts_with("-----")).is_some() {
+        rest.by_ref().find(|line| line.starts_with("-----"));
+        while rest.next_if(|line| line.trim().is_empty()).is_some() {}
+    }
+
+    let mut body = Vec::new();
+    let mut colophon = Vec::new();
+    let mut in_body = true;
+    for line in rest {
+        if line.starts_with("底本：") {
+            in_body = false;
+            colophon.push(line);
+        } else if !colophon.is_empty() {
+            colophon.push(line);
+        } else if line == "［＃本文終わり］" {
+            // 本文終わりから底本までの後書きは本文に含めない
+            in_body = false;
+        } else if in_body {
+            body.push(line);
+        }
+    }
+
+    Document {
+        header: lines[..header_end].to_vec(),
+        body: parse_body(&body),
+        colophon,
+    }
+}
+
+/// 本文の各行からレイアウト注記を除き、行の種類を判定する
+///
+/// 見出しと字下げは`ここから`〜`ここで…終わり`のブロックにも対応する
+pub fn parse_body(lines: &[&str]) -> Vec<Line> {
+    let mut heading_block = false;
+    let mut indent_block = false;
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut heading = heading_block;
+            let mut indented = indent_block;
+            for note in annotations(line) {
+                match layout(note) {
+                    Some((Layout::Heading, scope)) => {
+                        heading = true;
+                        match scope {
+                            Scope::Start => heading_block = true,
+                            Scope::End => heading_block = false,
+                            Scope::Line => {}
+                        }
+                    }
+                    Some((Layout::Indent, Scope::Start)) => {
+                        indent_block = true;
+                        indented = true;
+                    }
+                    Some((Layout::Indent, Scope::End)) => indent_block = false,
+                    // 行頭の注記は行全体の字下げ
+                    Some((Layout::Indent, Scope::Line))
+                        if line.starts_with(&format!("［＃{note}］")) =>
+                    {
+                        indented = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            let text = strip_layout(line);
+            let kind = if text.trim().is_empty() {
+                LineKind::Blank
+            } else if heading {
+                LineKind::Heading
+            } else if indented {
+                LineKind::Indented
+            } else {
+                LineKind::Text
+            };
+            Line { text, kind }
+        })
+        .collect()
+}
+
+/// 後付けの各項目 (`項目名：値`) から底本、初出、入力、校正を取り出す
+///
+/// 全角スペースで始まる行は直前の項目の続き (底本の発行年月日など) とみなす
+pub fn parse_citation(colophon: &[&str]) -> Citation {
+    let mut entries: Vec<(&str, String)> = Vec::new();
+    for line in colophon {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if line.starts_with(['　', ' '])
+            && let Some((_, value)) = entries.last_mut()
+        {
+            value.push(' ');
+            value.push_str(trimmed);
+        } else if let Some((key, value)) = trimmed.split_once('：') {
+            entries.push((key, value.trim().to_string()));
+        }
+    }
+
+    let entry = |key: &str| {
+        entries
+            .iter()
+            .find(|(k, value)| *k == key && !value.is_empty())
+            .map(|(_, value)| value.clone())
+    };
+    Citation {
+        base_text: entry("底本"),
+        first_published: entry("初出"),
+        inputter: entry("入力"),
+        proofreader: entry("校正"),
+    }
+}
+
+/// 注記の構造からレイアウト注記の種類と範囲を判定する
+///
+/// 対象を`「…」`で示す注記は`」は`以降の見出しのみをレイアウト注記とする
+/// (`［＃「見出し」に傍点］`などは対象の文字列に関わらずレイアウト注記ではない)
+fn layout(note: &str) -> Option<(Layout, Scope)> {
+    if ["改ページ", "改丁", "改段", "改見開き", "本文終わり"].contains(&note) {
+        return Some((Layout::Break, Scope::Line));
+    }
+    if note.starts_with('「') {
+        let (_, rest) = note.rsplit_once('」')?;
+        return is_heading(rest.strip_prefix('は')?).then_some((Layout::Heading, Scope::Line));
+    }
+
+    let (core, scope) = if let Some(core) = note.strip_prefix("ここから") {
+        (core, Scope::Start)
+    } else if let Some(core) = note.strip_suffix("終わり") {
+        (core.strip_prefix("ここで").unwrap_or(core), Scope::End)
+    } else {
+        (note, Scope::Line)
+    };
+
+    if is_heading(core) {
+        Some((Layout::Heading, scope))
+    } else if is_indent(core) {
+        Some((Layout::Indent, scope))
+    } else {
+        None
+    }
+}
+
+/// `見出し`、`大見出し`、`同行中見出し`、`窓小見出し`など
+fn is_heading(core: &str) -> bool {
+    let core = core
+        .strip_prefix("同行")
+        .or_else(|| core.strip_prefix("窓"))
+        .unwrap_or(core);
+    ["見出し", "大見出し", "中見出し", "小見出し"].contains(&core)
+}
+
+/// `３字下げ`、`天から２字下げ`、`２字下げ、折り返して３字下げ`、`地付き`、`地から１字上げ`など
+///
+/// 終わりの注記 (`字下げ`、`地付き`、`字上げ`) は字数を含まない
+fn is_indent(core: &str) -> bool {
+    core.split('、').all(|part| {
+        let part = ["天から", "地から", "折り返して", "改行天付き"]
+            .iter()
+            .fold(part, |part, prefix| {
+                part.strip_prefix(prefix).unwrap_or(part)
+            });
+        let amount = part
+            .strip_suffix("字下げ")
+            .or_else(|| part.strip_suffix("字上げ"));
+        match amount {
+            Some(amount) => amount.chars().all(|c| {
+                c.is_ascii_digit()
+                    || ('０'..='９').contains(&c)
+                    || "一二三四五六七八九十".contains(c)
+            }),
+            None => part == "地付き" || part.is_empty(),
+        }
+    }) && !core.is_empty()
+}
+
+/// 行内の注記 (`［＃…］`の中身)
+fn annotations(line: &str) -> Vec<&str> {
+    line.split("［＃")
+        .skip(1)
+        .filter_map(|part| part.split_once('］').map(|(note, _)| note))
+        .collect()
+}
+
+/// レイアウト注記を除く (外字`※［＃…］`や傍点などの注記は残す)
+fn strip_layout(line: &str) -> String {
+    let mut output = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("［＃") {
+        let Some(length) = rest[start..].find('］') else {
+            break;
+        };
+        let end = start + length + '］'.len_utf8();
+        let note = &rest[start + "［＃".len()..end - '］'.len_utf8()];
+        output.push_str(&rest[..start]);
+        if layout(note).is_none() {
+            output.push_str(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let lines = [
+            "坊っちゃん",
+            "夏目漱石",
+            "",
+            "-------------------------------------------------------",
+            "【テキスト中に現れる記号について】",
+            "",
+            "《》：ルビ",
+            "-------------------------------------------------------",
+            "",
+            "［＃３字下げ］一［＃「一」は大見出し］",
+            "",
+            "　親譲《おやゆず》りの無鉄砲《むてっぽう》で小供の時から損ばかりしている。",
+            "［＃改ページ］",
+            "［＃本文終わり］",
+            "後書き",
+            "",
+            "底本：「坊っちゃん」新潮文庫、新潮社",
+            "入力：真先芳秋",
+        ];
+        let document = parse(&lines);
+
+        assert_eq!(document.header, vec!["坊っちゃん", "夏目漱石"]);
+        assert_eq!(
+            document.colophon,
+            vec!["底本：「坊っちゃん」新潮文庫、新潮社", "入力：真先芳秋"]
+        );
+        assert_eq!(
+            document.body,
+            vec![
+                Line {
+                    text: "一".to_string(),
+                    kind: LineKind::Heading
+                },
+                Line {
+                    text: String::new(),
+                    kind: LineKind::Blank
+                },
+                Line {
+                    text:
+                        "　親譲《おやゆず》りの無鉄砲《むてっぽう》で小供の時から損ばかりしている。"
+                            .to_string(),
+                    kind: LineKind::Text
+                },
+                Line {
+                    text: String::new(),
+                    kind: LineKind::Blank
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_citation() {
+        let colophon = [
+            "底本：「夏目漱石全集２」ちくま文庫、筑摩書房",
+            "　　　1987（昭和62）年10月27日第1刷発行",
+            "底本の親本：「筑摩全集類聚版　夏目漱石全集　２」筑摩書房",
+            "　　　1971（昭和46）年4月5日初版発行",
+            "初出：「ホトトギス」",
+            "　　　1906（明治39）年4月",
+            "入力：Ｊｕｎ",
+            "校正：伊藤時也",
+            "",
+            "このファイルは、インターネットの図書館、青空文庫（https://www.aozora.gr.jp/）で作られました。",
+        ];
+        assert_eq!(
+            parse_citation(&colophon),
+            Citation {
+                base_text: Some(
+                    "「夏目漱石全集２」ちくま文庫、筑摩書房 1987（昭和62）年10月27日第1刷発行"
+                        .to_string()
+                ),
+                first_published: Some("「ホトトギス」 1906（明治39）年4月".to_string()),
+                inputter: Some("Ｊｕｎ".to_string()),
+                proofreader: Some("伊藤時也".to_string()),
+            }
+        );
+        assert_eq!(parse_citation(&[]), Citation::default());
+    }
+
+    #[test]
+    fn test_parse_body() {
+        let kinds = |lines: &[&str]| -> Vec<LineKind> {
+            parse_body(lines)
+                .into_iter()
+                .map(|line| line.kind)
+                .collect()
+        };
+
+        // 見出しのブロックと行内の見出し
+        assert_eq!(
+            kinds(&[
+                "［＃ここから中見出し］",
+                "第一章",
+                "［＃ここで中見出し終わり］",
+                "［＃大見出し］序［＃大見出し終わり］",
+                "　本文。",
+            ]),
+            vec![
+                LineKind::Blank,
+                LineKind::Heading,
+                LineKind::Blank,
+                LineKind::Heading,
+                LineKind::Text
+            ]
+        );
+
+        // 字下げのブロックと行頭の字下げ (行内の注記は字下げではない)
+        assert_eq!(
+            kinds(&[
+                "［＃ここから２字下げ］",
+                "　題辞。",
+                "［＃ここで字下げ終わり］",
+                "［＃地付き］一九二〇年",
+                "　本文※［＃「口＋世」、第3水準1-14-88］。",
+            ]),
+            vec![
+                LineKind::Blank,
+                LineKind::Indented,
+                LineKind::Blank,
+                LineKind::Indented,
+                LineKind::Text
+            ]
+        );
+
+        // 外字の注記は残す
+        assert_eq!(
+            parse_body(&["［＃２字下げ］本文※［＃「口＋世」、第3水準1-14-88］。"])[0].text,
+            "本文※［＃「口＋世」、第3水準1-14-88］。"
+        );
+
+        // 対象の文字列に見出し・字下げを含む注記はレイアウト注記ではない
+        let lines =
+            parse_body(&["　見出し［＃「見出し」に傍点］の字下げ［＃「字下げ」は縦中横］。"]);
+        assert_eq!(lines[0].kind, LineKind::Text);
+        assert_eq!(
+            lines[0].text,
+            "　見出し［＃「見出し」に傍点］の字下げ［＃「字下げ」は縦中横］。"
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(
+            layout("「一」は大見出し"),
+            Some((Layout::Heading, Scope::Line))
+        );
+        assert_eq!(
+            layout("ここから窓中見出し"),
+            Some((Layout::Heading, Scope::Start))
+        );
+        assert_eq!(
+            layout("ここで小見出し終わり"),
+            Some((Layout::Heading, Scope::End))
+        );
+        assert_eq!(
+            layout("ここから２字下げ、折り返して３字下げ"),
+            Some((Layout::Indent, Scope::Start))
+        );
+        assert_eq!(
+            layout("地から２字上げ"),
+            Some((Layout::Indent, Scope::Line))
+        );
+        assert_eq!(
+            layout("ここで字下げ終わり"),
+            Some((Layout::Indent, Scope::End))
+        );
+        assert_eq!(layout("本文終わり"), Some((Layout::Break, Scope::Line)));
+        assert_eq!(layout("「見出し」に傍点"), None);
+        assert_eq!(layout("見出し付きの表"), None);
+        assert_eq!(layout("字下げ位置の誤りは底本通り"), None);
+    }
+}
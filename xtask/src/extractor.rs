@@ -1,15 +1,25 @@
-use anyhow::{Context, Result};
+use aozora_core::accent::convert_accent;
+use aozora_core::char_type::CharType;
+use aozora_core::encoding::decode_to_utf8;
+use aozora_core::gaiji::convert_gaiji;
+use aozora_core::token::Token;
+use aozora_core::tokenizer::tokenize;
 use aozora_core::zip::read_first_txt_from_zip;
-use aozora2::strip::convert;
-use scraper::{Html, Selector};
+use aozora2::strip::convert_line;
+use encoding_rs::{EUC_JP, Encoding, SHIFT_JIS, UTF_8};
+use scraper::{ElementRef, Html, Node, Selector};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use url::Url;
 
-use crate::models::{Author, Work, WorkLink};
+use crate::document::{self, LineKind};
+use crate::models::{Author, Body, Opening, Source, Work, WorkLink};
+
+const AOZORA_CARDS_URL: &str = "https://www.aozora.gr.jp/cards/";
 
 /// 著者一覧を抽出 (person_all.htmlより)
 pub fn extract_authors(author_list_path: &Path) -> Option<Vec<Author>> {
-    let document = read_html(author_list_path).ok()?;
+    let document = read_html(author_list_path)?;
     let index_pages_dir = author_list_path.parent().unwrap_or(Path::new("."));
 
     let list_item_selector = Selector::parse("ol > li").unwrap();
@@ -27,6 +37,7 @@ pub fn extract_authors(author_list_path: &Path) -> Option<Vec<Author>> {
                 parse_id_from_href(href, "person", ".html").map(|id| {
                     let page_path = index_pages_dir.join(format!("person{}.html", id));
                     Author {
+                        id,
                         name,
                         page_path: page_path.to_string_lossy().to_string(),
                     }
@@ -47,7 +58,7 @@ pub fn extract_works(author: &Author) -> Option<Vec<Work>> {
     }
 
     let author_page_path = Path::new(&author.page_path);
-    let document = read_html(author_page_path).ok()?;
+    let document = read_html(author_page_path)?;
     let cards_dir = author_page_path
         .parent()
         .and_then(|p| p.parent())
@@ -76,6 +87,7 @@ pub fn extract_works(author: &Author) -> Option<Vec<Work>> {
                         .and_then(|s| s.split('/').next())?;
                     let page_path = cards_dir.join(author_id).join(format!("card{}.html", id));
                     Some(Work {
+                        id,
                         title,
                         page_path: page_path.to_string_lossy().to_string(),
                     })
@@ -87,24 +99,25 @@ pub fn extract_works(author: &Author) -> Option<Vec<Work>> {
     Some(works)
 }
 
-/// 作品のzip/htmlファイルパスを抽出
+/// 作品のzip/htmlファイルパスとURLを抽出
 ///
 /// ダウンロードデータtable内の全行をリスト化し、その中から条件に合致する行を抽出
 /// 1. リンク先が存在し"./files"で始まる行
 /// 2. 末尾が`.zip`で終わる行をテキストzipファイルリンクとみなす
 ///   - "ttz_zip"は除外
 ///   - 複数行となった場合は最初の1行のみ
-///   - 存在しない場合は処理を停止しNoneを返す
-/// 3. 末尾が`.html`または`.htm`で終わる行をHTMLファイルリンクとみなす
 ///   - 存在しない場合はNoneとする
+/// 3. 末尾が`.html`または`.htm`で終わる行をHTMLファイルリンクとみなし、図書カードのURLを基準にURLを組み立てる
+///   - 存在しない場合はNoneとする
+/// 4. zipファイルとHTMLファイルのいずれも存在しない場合はNoneを返す
 pub fn extract_links(work_page_path: &Path) -> Option<WorkLink> {
     if !work_page_path.exists() {
         return None;
     }
 
-    let aozora_url = "https://www.aozora.gr.jp";
+    let card_url = card_url(work_page_path)?;
     let work_dir = work_page_path.parent().unwrap_or(Path::new("."));
-    let document = read_html(work_page_path).ok()?;
+    let document = read_html(work_page_path)?;
     let table_selector = Selector::parse(r#"table.download"#).ok()?;
     let link_selector = Selector::parse("a[href]").ok()?;
 
@@ -121,78 +134,437 @@ pub fn extract_links(work_page_path: &Path) -> Option<WorkLink> {
         return None;
     }
 
+    let local_path = |link: &String| {
+        work_dir
+            .join(link.trim_start_matches("./"))
+            .to_string_lossy()
+            .to_string()
+    };
+
     // zipパスを抽出
     let zip_path = links
         .iter()
-        .filter(|link| link.starts_with("./files"))
         .filter(|link| link.ends_with(".zip"))
         .find(|link| !link.contains("_ttz.zip") && !link.ends_with("ttz.zip"))
-        .map(|link| {
-            work_dir
-                .join(link.trim_start_matches("./"))
-                .to_string_lossy()
-                .to_string()
-        })?
-        .clone();
-    if zip_path.is_empty() {
-        return None;
-    }
+        .map(local_path);
 
-    // htmlパスを抽出
-    let html_path = links
+    // htmlパスとURLを抽出
+    let html_link = links
         .iter()
-        .filter(|link| link.starts_with("./files"))
-        .find(|link| link.ends_with(".html") || link.ends_with(".htm"))
-        .map(|link| {
-            work_dir
-                .join(link.trim_start_matches("./"))
-                .to_string_lossy()
-                .to_string()
-                .replace("aozorabunko", aozora_url)
-        })
-        .clone();
+        .find(|link| link.ends_with(".html") || link.ends_with(".htm"));
+    if zip_path.is_none() && html_link.is_none() {
+        return None;
+    }
 
     Some(WorkLink {
         zip_path,
-        url: html_path,
+        html_path: html_link.map(local_path),
+        url: html_link.and_then(|link| card_url.join(link).ok()),
+        card_url,
     })
 }
 
-/// zipファイルから書き出しテキストを抽出
-pub fn extract_text_from_zip(zip_path: &Path) -> Option<String> {
+/// 図書カードのURL
+///
+/// 例: `aozorabunko/cards/000148/card769.html` -> `https://www.aozora.gr.jp/cards/000148/card769.html`
+pub fn card_url(work_page_path: &Path) -> Option<Url> {
+    let card = work_page_path.file_name()?.to_str()?;
+    let person = work_page_path.parent()?.file_name()?.to_str()?;
+    Url::parse(AOZORA_CARDS_URL)
+        .ok()?
+        .join(&format!("{person}/{card}"))
+        .ok()
+}
+
+/// 図書カードのURLに対応するリポジトリ内のパス (`card_url`の逆)
+///
+/// 例: `https://www.aozora.gr.jp/cards/000148/card769.html` -> `aozorabunko/cards/000148/card769.html`
+pub fn card_path(aozorabunko: &Path, card_url: &str) -> Option<PathBuf> {
+    let relative = card_url.strip_prefix(AOZORA_CARDS_URL)?;
+    Some(aozorabunko.join("cards").join(relative))
+}
+
+/// 青空文庫の図書カードまたは作品ファイルのURLであることを検証する
+///
+/// `https://www.aozora.gr.jp/cards/{人物ID 6桁}/card{作品ID}.html` または
+/// `https://www.aozora.gr.jp/cards/{人物ID 6桁}/files/{ファイル名}.html`
+pub fn validate_url(url: &str) -> Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL {url}: {e}"))?;
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let is_person = |s: &str| s.len() == 6 && s.bytes().all(|b| b.is_ascii_digit());
+    let is_card = |s: &str| {
+        parse_id_from_href(s, "card", ".html")
+            .is_some_and(|id| id.bytes().all(|b| b.is_ascii_digit()))
+    };
+    let is_file = |s: &str| !s.is_empty() && (s.ends_with(".html") || s.ends_with(".htm"));
+
+    let valid = parsed.scheme() == "https"
+        && parsed.host_str() == Some("www.aozora.gr.jp")
+        && parsed.query().is_none()
+        && match segments.as_slice() {
+            ["cards", person, card] => is_person(person) && is_card(card),
+            ["cards", person, "files", file] => is_person(person) && is_file(file),
+            _ => false,
+        };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Not an Aozora Bunko card or file URL: {url}"))
+    }
+}
+
+/// 作家ページ/図書カードの表から読みなどの項目を抽出
+///
+/// 例: `<td class="header">作品名読み：</td><td>ぼっちゃん</td>` -> Some("ぼっちゃん")
+pub fn extract_reading(page_path: &Path, label: &str) -> Option<String> {
+    if !page_path.exists() {
+        return None;
+    }
+    let document = read_html(page_path)?;
+    let header_selector = Selector::parse("td.header").ok()?;
+
+    document
+        .select(&header_selector)
+        .find(|td| td.text().collect::<String>().trim().trim_end_matches('：') == label)
+        .and_then(|td| td.next_siblings().find_map(scraper::ElementRef::wrap))
+        .map(|td| td.text().collect::<String>().trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 作品の本文の行を読み込む
+///
+/// テキストzipファイルがない (読めない) 場合はXHTMLファイルから読み込む
+pub fn read_body(work_link: &WorkLink) -> Option<Body> {
+    work_link
+        .zip_path
+        .as_deref()
+        .and_then(|path| read_body_from_zip(Path::new(path)))
+        .or_else(|| {
+            work_link
+                .html_path
+                .as_deref()
+                .and_then(|path| read_body_from_html(Path::new(path)))
+        })
+}
+
+/// zipファイル内のテキストファイルの本文 (ヘッダ、記号の説明、後付けを除く) の行
+fn read_body_from_zip(zip_path: &Path) -> Option<Body> {
     let bytes = read_first_txt_from_zip(zip_path).ok()?;
-    let text = convert(&bytes);
-
-    // 書き出しとみなす条件
-    // - 全角スペースで始まる
-    // - `。`を含む
-    // 最初の`。`までを抽出
-    //
-    // TODO: 条件について未検証
-    // NG例:
-    // 村山 籌子,〈ピツコロさん〉,「さよなら年寄の学者さん。,https://www.aozora.gr.jp/cards/001172/files/44946_50976.html
-    // 宮本 百合子,バルザック,「幻滅」より。,https://www.aozora.gr.jp/cards/000311/files/4232_14811.html
-    // 倉田 百三,出家とその弟子,松若（その息。,https://www.aozora.gr.jp/cards/000256/files/1412_24371.html
-    // 宮沢 賢治,『春と修羅』補遺,何回か東京で引っぱられた。,https://www.aozora.gr.jp/cards/000081/files/43040_15400.html
-    // 太宰 治,人間失格,私は、その男の写真を三葉、見たことがある。,https://www.aozora.gr.jp/cards/000035/files/301_14912.html
-    let first_line = text
-        .lines()
-        .filter(|line| line.starts_with('　'))
-        .find(|line| line.contains('。'))
-        .map(|line| line.trim_start_matches('　'))
-        .map(|line| line.split('。').next().unwrap_or("").to_string() + "。")
+    let text = decode_to_utf8(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let parsed = document::parse(&lines);
+
+    Some(Body {
+        citation: document::parse_citation(&parsed.colophon),
+        lines: parsed.body,
+        source: Source::Text,
+    })
+}
+
+/// XHTMLファイルの本文を青空文庫形式に変換した行
+///
+/// テキストzipファイルがない作品に使用する。本文 (`div.main_text`) を
+/// 青空文庫形式の行 (ルビは`｜親文字《ルビ》`、外字は`※［＃…］`) に変換し、
+/// テキストファイルと同じ条件で書き出しを探せるようにする。
+/// 書誌情報は後付け (`div.bibliographical_information`) から読み込む
+fn read_body_from_html(html_path: &Path) -> Option<Body> {
+    let html = read_html(html_path)?;
+    let main_text_selector = Selector::parse("div.main_text").ok()?;
+    let main_text = html.select(&main_text_selector).next()?;
+    let colophon_selector = Selector::parse("div.bibliographical_information").ok()?;
+
+    let mut text = String::new();
+    xhtml_to_aozora(main_text, &mut text);
+    let mut colophon = String::new();
+    if let Some(element) = html.select(&colophon_selector).next() {
+        xhtml_to_aozora(element, &mut colophon);
+    }
+
+    Some(Body {
+        lines: document::parse_body(&text.lines().collect::<Vec<_>>()),
+        source: Source::Xhtml,
+        citation: document::parse_citation(&colophon.lines().collect::<Vec<_>>()),
+    })
+}
+
+/// 本文の行から書き出しを抽出
+///
+/// 書き出しとみなす条件
+/// - 本文の段落 (見出し、字下げされた引用・題辞などを除く)
+/// - `。`を含む
+/// - 同じまとまりの中では全角スペースで始まる行を優先する
+///
+/// 最初の`。`までを抽出
+///
+/// TODO: 条件について未検証
+/// NG例:
+/// 村山 籌子,〈ピツコロさん〉,「さよなら年寄の学者さん。,https://www.aozora.gr.jp/cards/001172/files/44946_50976.html
+/// 宮本 百合子,バルザック,「幻滅」より。,https://www.aozora.gr.jp/cards/000311/files/4232_14811.html
+/// 倉田 百三,出家とその弟子,松若（その息。,https://www.aozora.gr.jp/cards/000256/files/1412_24371.html
+/// 宮沢 賢治,『春と修羅』補遺,何回か東京で引っぱられた。,https://www.aozora.gr.jp/cards/000081/files/43040_15400.html
+/// 太宰 治,人間失格,私は、その男の写真を三葉、見たことがある。,https://www.aozora.gr.jp/cards/000035/files/301_14912.html
+pub fn extract_opening(body: &Body) -> Opening {
+    opening_line(body)
+        .and_then(|number| extract_opening_at(body, number))
         .unwrap_or_default()
-        .to_string();
+}
+
+/// 書き出しとみなす行の行番号 (1始まり)
+///
+/// `。`を含む最初の段落の行を選ぶ。その行から続く段落の行 (空行・見出し・字下げの前まで) に
+/// 全角スペースで始まる行があればそちらを優先する (段落の前の登場人物一覧や日付などを除くため)
+pub fn opening_line(body: &Body) -> Option<usize> {
+    let plain = |index: usize| convert_line(&body.lines[index].text);
+    let is_text = |index: &usize| body.lines[*index].kind == LineKind::Text;
+    let is_sentence = |index: &usize| is_text(index) && plain(*index).contains('。');
+
+    let first = (0..body.lines.len()).find(is_sentence)?;
+    let indented = (first..body.lines.len())
+        .take_while(is_text)
+        .filter(is_sentence)
+        .find(|index| plain(*index).starts_with('　'));
+    Some(indented.unwrap_or(first) + 1)
+}
+
+/// 指定した行 (1始まり) の最初の文を書き出しとして抽出
+///
+/// 書き出しの条件は問わない (`。`を含まない場合は行全体)
+pub fn extract_opening_at(body: &Body, number: usize) -> Option<Opening> {
+    let line = &body.lines.get(number.checked_sub(1)?)?.text;
+    let text = first_sentence(&convert_line(line));
+    let ruby = first_sentence(&convert_line_with_ruby(line));
+    Some(Opening {
+        ruby: (ruby != text).then_some(ruby),
+        text,
+        source: body.source,
+    })
+}
+
+/// XHTMLの要素を青空文庫形式のテキストに変換する
+///
+/// - `<br>`とブロック要素 (見出し、`div`) で改行する
+/// - 見出しと字下げ (`div.jisage_N`) はテキストファイルと同じレイアウト注記で囲む
+/// - `<ruby>`は`｜親文字《ルビ》`に変換する
+/// - 外字画像は`alt`の説明から`※［＃…］`に変換する
+/// - 注記 (`span.notes`) は除去する
+fn xhtml_to_aozora(element: ElementRef<'_>, output: &mut String) {
+    for child in element.children() {
+        if let Node::Text(text) = child.value() {
+            output.push_str(&text.replace(['\r', '\n'], ""));
+            continue;
+        }
+        let Some(child) = ElementRef::wrap(child) else {
+            continue;
+        };
+        let has_class = |class: &str| child.value().classes().any(|c| c == class);
+
+        match child.value().name() {
+            "br" => output.push('\n'),
+            "ruby" => {
+                let text_of = |names: &[&str]| -> String {
+                    child
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .filter(|e| names.contains(&e.value().name()))
+                        .flat_map(|e| e.text())
+                        .collect()
+                };
+                output.push('｜');
+                output.push_str(&text_of(&["rb"]));
+                output.push('《');
+                output.push_str(&text_of(&["rt"]));
+                output.push('》');
+            }
+            "img" if has_class("gaiji") => {
+                let alt = child.value().attr("alt").unwrap_or("※");
+                match alt.strip_prefix("※(").and_then(|a| a.strip_suffix(')')) {
+                    Some(description) => output.push_str(&format!("※［＃{description}］")),
+                    None => output.push_str(alt),
+                }
+            }
+            "span" if has_class("notes") => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                output.push_str("\n［＃見出し］");
+                xhtml_to_aozora(child, output);
+                output.push_str("［＃見出し終わり］\n");
+            }
+            "div" if child.value().classes().any(|c| c.starts_with("jisage_")) => {
+                output.push_str("\n［＃ここから字下げ］\n");
+                xhtml_to_aozora(child, output);
+                output.push_str("\n［＃ここで字下げ終わり］\n");
+            }
+            "div" => {
+                output.push('\n');
+                xhtml_to_aozora(child, output);
+                output.push('\n');
+            }
+            _ => xhtml_to_aozora(child, output),
+        }
+    }
+}
+
+/// 行頭の全角スペースを除き、最初の`。`までを抽出 (`。`を含まない場合は行全体)
+fn first_sentence(line: &str) -> String {
+    let line = line.trim_start_matches('　');
+    match line.split_once('。') {
+        Some((sentence, _)) => sentence.to_string() + "。",
+        None => line.to_string(),
+    }
+}
+
+/// 青空文庫形式の1行からルビ以外の注記を除去する
+///
+/// ルビは`｜親文字《ルビ》`形式に正規化して残す
+/// 暗黙ルビ (`漢字《かんじ》`) は直前の同じ文字種の連続を親文字とみなす
+fn convert_line_with_ruby(line: &str) -> String {
+    let mut output = String::new();
+
+    for token in tokenize(line) {
+        match token {
+            Token::Ruby { children } => {
+                let base_start = ruby_base_start(&output);
+                if base_start == output.len() {
+                    // 親文字が見つからない場合はルビを捨てる
+                    continue;
+                }
+                output.insert(base_start, '｜');
+                output.push('《');
+                output.push_str(&convert_line_tokens(&children));
+                output.push('》');
+            }
+            Token::PrefixedRuby {
+                base_children,
+                ruby_children,
+            } => {
+                output.push('｜');
+                output.push_str(&convert_line_tokens(&base_children));
+                output.push('《');
+                output.push_str(&convert_line_tokens(&ruby_children));
+                output.push('》');
+            }
+            token => output.push_str(&convert_line_tokens(&[token])),
+        }
+    }
+
+    output
+}
+
+/// トークン列をルビ・注記を除去したテキストに変換
+fn convert_line_tokens(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::Text(s) => s.clone(),
+            Token::Ruby { .. } | Token::Command { .. } => String::new(),
+            Token::PrefixedRuby { base_children, .. } => convert_line_tokens(base_children),
+            Token::Gaiji { description } => convert_gaiji(description),
+            Token::Accent { children } => convert_accent(&convert_line_tokens(children)),
+        })
+        .collect()
+}
+
+/// 暗黙ルビの親文字の開始位置 (末尾の同じ文字種の連続の先頭)
+fn ruby_base_start(text: &str) -> usize {
+    let Some(last) = text.chars().last().map(CharType::classify) else {
+        return text.len();
+    };
+    if !last.can_be_ruby_base() {
+        return text.len();
+    }
 
-    Some(first_line)
+    text.char_indices()
+        .rev()
+        .take_while(|(_, c)| CharType::classify(*c) == last)
+        .last()
+        .map_or(text.len(), |(i, _)| i)
 }
 
 /// HTMLファイルを読み込みパースする
-fn read_html(path: &Path) -> Result<Html> {
-    let content = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
-    let html_str = String::from_utf8(content)
-        .with_context(|| format!("Failed to parse UTF-8 from {:?}", path))?;
-    Ok(Html::parse_document(&html_str))
+///
+/// 読み込みに失敗した場合、文字コードの判定やデコードに問題があった場合は
+/// ファイルごとに警告を出力する
+fn read_html(path: &Path) -> Option<Html> {
+    let bytes = fs::read(path)
+        .map_err(|e| warn(path, &format!("Failed to read: {e}")))
+        .ok()?;
+    let (text, warnings) = decode_html(&bytes);
+    for warning in warnings {
+        warn(path, &warning);
+    }
+    Some(Html::parse_document(&text))
+}
+
+/// HTMLのバイト列を文字コードを判定してデコードする
+///
+/// 1. BOM
+/// 2. `<meta charset>`/`<meta http-equiv content>`/XML宣言の指定
+///    (指定の文字コードで不正なバイト列がある場合は3.を試す)
+/// 3. UTF-8、Shift_JIS、EUC-JPのうち不正なバイト列なしにデコードできるもの
+///
+/// いずれでもデコードできない場合はShift_JIS (不正なバイト列は置換) とする
+fn decode_html(bytes: &[u8]) -> (String, Vec<String>) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        let warnings = had_errors
+            .then(|| format!("Invalid {} byte sequences replaced", encoding.name()))
+            .into_iter()
+            .collect();
+        return (text.into_owned(), warnings);
+    }
+
+    let mut warnings = Vec::new();
+    let declared = declared_charset(bytes);
+    if let Some(encoding) = declared {
+        let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+        if !had_errors {
+            return (text.into_owned(), warnings);
+        }
+    }
+
+    for encoding in [UTF_8, SHIFT_JIS, EUC_JP] {
+        let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+        if !had_errors {
+            if let Some(declared) = declared {
+                warnings.push(format!(
+                    "Declared charset {} does not match content, decoded as {}",
+                    declared.name(),
+                    encoding.name()
+                ));
+            }
+            return (text.into_owned(), warnings);
+        }
+    }
+
+    let encoding = declared.unwrap_or(SHIFT_JIS);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    warnings.push(format!(
+        "Invalid {} byte sequences replaced",
+        encoding.name()
+    ));
+    (text.into_owned(), warnings)
+}
+
+/// 先頭1024バイトから`charset=`またはXML宣言の`encoding=`の指定を探す
+///
+/// ASCII互換でない文字コード (UTF-16等) の指定は無視する
+fn declared_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_ascii_lowercase();
+    ["charset=", "encoding="].iter().find_map(|key| {
+        let value = &head[head.find(key)? + key.len()..];
+        let label: String = value
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+            .collect();
+        Encoding::for_label(label.as_bytes()).filter(|encoding| encoding.is_ascii_compatible())
+    })
+}
+
+fn warn(path: &Path, message: &str) {
+    eprintln!("warning: {}: {message}", path.display());
 }
 
 /// href文字列からID部分をパースして抽出
@@ -222,18 +594,207 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_card_url() {
+        let card_url = card_url(Path::new("aozorabunko/cards/000148/card769.html")).unwrap();
+        assert_eq!(
+            card_url.as_str(),
+            "https://www.aozora.gr.jp/cards/000148/card769.html"
+        );
+        assert_eq!(
+            card_url.join("./files/769_14939.html").unwrap().as_str(),
+            "https://www.aozora.gr.jp/cards/000148/files/769_14939.html"
+        );
+        assert_eq!(
+            card_path(Path::new("aozorabunko"), card_url.as_str()),
+            Some(PathBuf::from("aozorabunko/cards/000148/card769.html"))
+        );
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://www.aozora.gr.jp/cards/000148/card769.html").is_ok());
+        assert!(validate_url("https://www.aozora.gr.jp/cards/000148/files/769_14939.html").is_ok());
+        assert!(
+            validate_url("../https://www.aozora.gr.jp/cards/000148/files/769_14939.html").is_err()
+        );
+        assert!(validate_url("https://www.aozora.gr.jp/cards/148/card769.html").is_err());
+        assert!(
+            validate_url("https://www.aozora.gr.jp/cards/000148/files/769_ruby_565.zip").is_err()
+        );
+        assert!(validate_url("http://www.aozora.gr.jp/cards/000148/card769.html").is_err());
+    }
+
+    #[test]
+    fn test_convert_line_with_ruby() {
+        assert_eq!(
+            convert_line_with_ruby("吾輩《わがはい》は猫である。名前はまだ無い。"),
+            "｜吾輩《わがはい》は猫である。名前はまだ無い。"
+        );
+        assert_eq!(
+            convert_line_with_ruby("　親譲《おやゆず》りの無鉄砲《むてっぽう》で"),
+            "　｜親譲《おやゆず》りの｜無鉄砲《むてっぽう》で"
+        );
+        assert_eq!(
+            convert_line_with_ruby("｜東京《とうきょう》へ行く［＃「行く」に傍点］"),
+            "｜東京《とうきょう》へ行く"
+        );
+    }
+
+    #[test]
+    fn test_extract_reading() {
+        let page_path = std::env::temp_dir().join("kakidashi-test-card.html");
+        fs::write(
+            &page_path,
+            r#"<html><body><table summary="タイトルデータ">
+<tr><td class="header">作品名：</td><td><font size="+2">坊っちゃん</font></td></tr>
+<tr><td class="header">作品名読み：</td><td>ぼっちゃん</td></tr>
+</table></body></html>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            extract_reading(&page_path, "作品名読み"),
+            Some("ぼっちゃん".to_string())
+        );
+        assert_eq!(extract_reading(&page_path, "作家名読み"), None);
+    }
+
+    #[test]
+    fn test_read_body_from_html() {
+        let html = r#"<?xml version="1.0" encoding="Shift_JIS"?>
+<html><body>
+<h1 class="title">坊っちゃん</h1>
+<div class="main_text"><br />
+<h3 class="o-midashi"><a class="midashi_anchor" id="midashi10">一</a></h3><br />
+<br />
+　<ruby><rb>親譲</rb><rp>（</rp><rt>おやゆず</rt><rp>）</rp></ruby>りの<ruby><rb>無鉄砲</rb><rp>（</rp><rt>むてっぽう</rt><rp>）</rp></ruby>で小供の時から損ばかりして居る。<span class="notes">［＃「居る」に傍点］</span>小学校に居る時分学校の二階から飛び降りて一週間ほど腰を抜かした事がある。<br />
+</div>
+<div class="bibliographical_information">
+<hr />
+<br />
+底本：「夏目漱石全集２」ちくま文庫、筑摩書房<br />
+　　　1987（昭和62）年10月27日第1刷発行<br />
+入力：Ｊｕｎ<br />
+校正：伊藤時也<br />
+</div>
+</body></html>"#;
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(html);
+        let html_path = std::env::temp_dir().join("kakidashi-test-work.html");
+        fs::write(&html_path, bytes).unwrap();
+
+        let body = read_body_from_html(&html_path).unwrap();
+        assert_eq!(
+            body.citation.base_text.as_deref(),
+            Some("「夏目漱石全集２」ちくま文庫、筑摩書房 1987（昭和62）年10月27日第1刷発行")
+        );
+        assert_eq!(body.citation.proofreader.as_deref(), Some("伊藤時也"));
+
+        let opening = extract_opening(&body);
+        assert_eq!(
+            opening.text,
+            "親譲りの無鉄砲で小供の時から損ばかりして居る。"
+        );
+        assert_eq!(
+            opening.ruby,
+            Some(
+                "｜親譲《おやゆず》りの｜無鉄砲《むてっぽう》で小供の時から損ばかりして居る。"
+                    .to_string()
+            )
+        );
+        assert_eq!(opening.source, Source::Xhtml);
+    }
+
+    #[test]
+    fn test_decode_html() {
+        let html = |charset: &str| {
+            format!(
+                r#"<html><head><meta http-equiv="Content-Type" content="text/html;charset={charset}" /></head><body>吾輩は猫である</body></html>"#
+            )
+        };
+
+        let html_sjis = html("Shift_JIS");
+        let (bytes, _, _) = SHIFT_JIS.encode(&html_sjis);
+        assert_eq!(decode_html(&bytes), (html("Shift_JIS"), vec![]));
+
+        let html_euc = html("EUC-JP");
+        let (bytes, _, _) = EUC_JP.encode(&html_euc);
+        assert_eq!(decode_html(&bytes), (html("EUC-JP"), vec![]));
+
+        // BOM付きUTF-8
+        let bytes = [b"\xEF\xBB\xBF".as_slice(), html("Shift_JIS").as_bytes()].concat();
+        assert_eq!(decode_html(&bytes), (html("Shift_JIS"), vec![]));
+
+        // 指定と内容が異なる場合は内容から判定する
+        let (bytes, _, _) = EUC_JP.encode(&html_sjis);
+        let (text, warnings) = decode_html(&bytes);
+        assert_eq!(text, html("Shift_JIS"));
+        assert_eq!(
+            warnings,
+            vec!["Declared charset Shift_JIS does not match content, decoded as EUC-JP"]
+        );
+
+        // 指定なし
+        let (bytes, _, _) = SHIFT_JIS.encode("<p>吾輩は猫である</p>");
+        assert_eq!(
+            decode_html(&bytes),
+            ("<p>吾輩は猫である</p>".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_opening_line() {
+        let line = |lines: &[&str]| {
+            opening_line(&Body {
+                lines: document::parse_body(lines),
+                source: Source::Text,
+                citation: Default::default(),
+            })
+        };
+
+        // 見出しと字下げされた題辞を飛ばし、全角スペースで始まらない会話文の段落も書き出しとする
+        assert_eq!(
+            line(&[
+                "［＃「一」は大見出し］",
+                "［＃ここから２字下げ］",
+                "　題辞。",
+                "［＃ここで字下げ終わり］",
+                "",
+                "「おい、起きろ。」と言った。",
+            ]),
+            Some(6)
+        );
+        // 同じまとまりの中では全角スペースで始まる行を優先する
+        assert_eq!(
+            line(&[
+                "松若（その息。",
+                "　親譲りの無鉄砲で小供の時から損ばかりして居る。"
+            ]),
+            Some(2)
+        );
+        assert_eq!(line(&["［＃改ページ］", "題"]), None);
+    }
+
+    #[test]
+    fn test_first_sentence() {
+        assert_eq!(
+            first_sentence("　吾輩は猫である。名前はまだ無い。"),
+            "吾輩は猫である。"
+        );
+        assert_eq!(first_sentence("　一"), "一");
+    }
+
     #[test]
     // テキストファイルとHTMLファイルがともに1つずつ存在するケース
     fn test_extract_ruby_zip_path() {
         let work_page_path = Path::new("../aozorabunko/cards/000006/card47064.html");
         let work_link = extract_links(work_page_path).unwrap();
         assert_eq!(
-            work_link.zip_path,
+            work_link.zip_path.unwrap(),
             "../aozorabunko/cards/000006/files/47064_txt_31250.zip".to_string()
         );
         assert_eq!(
-            work_link.url,
-            Some("../https://www.aozora.gr.jp/cards/000006/files/47064_31847.html".to_string())
+            work_link.url.map(String::from),
+            Some("https://www.aozora.gr.jp/cards/000006/files/47064_31847.html".to_string())
         );
     }
 
@@ -241,8 +802,9 @@ mod tests {
     // テキストファイルを含まないケース
     fn test_extract_only_html_zip_path() {
         let work_page_path = Path::new("../aozorabunko/cards/001529/card409.html");
-        let work_link = extract_links(work_page_path);
-        assert!(work_link.is_none());
+        let work_link = extract_links(work_page_path).unwrap();
+        assert!(work_link.zip_path.is_none());
+        assert!(work_link.html_path.is_some());
     }
 
     #[test]
@@ -251,12 +813,12 @@ mod tests {
         let work_page_path = Path::new("../aozorabunko/cards/001393/card54926.html");
         let work_link = extract_links(work_page_path).unwrap();
         assert_eq!(
-            work_link.zip_path,
+            work_link.zip_path.unwrap(),
             "../aozorabunko/cards/001393/files/54926_txt_47247.zip".to_string()
         );
         assert_eq!(
-            work_link.url,
-            Some("../https://www.aozora.gr.jp/cards/001393/files/54926_53265.html".to_string())
+            work_link.url.map(String::from),
+            Some("https://www.aozora.gr.jp/cards/001393/files/54926_53265.html".to_string())
         );
     }
 
@@ -266,12 +828,16 @@ mod tests {
         let work_page_path = Path::new("../aozorabunko/cards/000148/card769.html");
         let work_link = extract_links(work_page_path).unwrap();
         assert_eq!(
-            work_link.zip_path,
+            work_link.zip_path.unwrap(),
             "../aozorabunko/cards/000148/files/769_ruby_565.zip".to_string()
         );
         assert_eq!(
-            work_link.url,
-            Some("../https://www.aozora.gr.jp/cards/000148/files/769_14939.html".to_string())
+            work_link.url.map(String::from),
+            Some("https://www.aozora.gr.jp/cards/000148/files/769_14939.html".to_string())
+        );
+        assert_eq!(
+            work_link.card_url.as_str(),
+            "https://www.aozora.gr.jp/cards/000148/card769.html"
         );
     }
 
@@ -281,12 +847,12 @@ mod tests {
         let work_page_path = Path::new("../aozorabunko/cards/001393/card54926.html");
         let work_link = extract_links(work_page_path).unwrap();
         assert_eq!(
-            work_link.zip_path,
+            work_link.zip_path.unwrap(),
             "../aozorabunko/cards/001393/files/54926_txt_47247.zip".to_string()
         );
         assert_eq!(
-            work_link.url,
-            Some("../https://www.aozora.gr.jp/cards/001393/files/54926_53265.html".to_string())
+            work_link.url.map(String::from),
+            Some("https://www.aozora.gr.jp/cards/001393/files/54926_53265.html".to_string())
         );
     }
 }
@@ -0,0 +1,109 @@
+use anyhow::{Ok, Result};
+use std::fs::{File, create_dir_all};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::models::WorkRecord;
+
+/// strfile形式のバージョン
+const STRFILE_VERSION: u32 = 2;
+/// エントリの区切り文字
+const DELIMITER: u8 = b'%';
+
+/// fortune(6)形式のファイルとstrfile(8)形式のインデックス (`.dat`) を書き出す
+pub fn export_fortune(records: &[WorkRecord], output_path: &Path) -> Result<()> {
+    if let Some(parent) = output_path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let mut content = Vec::new();
+    let mut offsets = vec![0u32];
+    let mut lengths = Vec::new();
+    for record in records {
+        let entry = entry(record);
+        content.extend_from_slice(entry.as_bytes());
+        content.extend_from_slice(&[DELIMITER, b'\n']);
+        lengths.push(u32::try_from(entry.len())?);
+        offsets.push(u32::try_from(content.len())?);
+    }
+    File::create(output_path)?.write_all(&content)?;
+
+    let mut dat = Vec::new();
+    for value in [
+        STRFILE_VERSION,
+        u32::try_from(records.len())?,
+        lengths.iter().copied().max().unwrap_or(0),
+        lengths.iter().copied().min().unwrap_or(0),
+        0, // flags
+    ] {
+        dat.extend_from_slice(&value.to_be_bytes());
+    }
+    dat.extend_from_slice(&[DELIMITER, 0, 0, 0]);
+    for offset in offsets {
+        dat.extend_from_slice(&offset.to_be_bytes());
+    }
+    File::create(dat_path(output_path))?.write_all(&dat)?;
+
+    Ok(())
+}
+
+/// strfile(8)形式のインデックスのパス (拡張子を置き換えず`.dat`を付ける: `quotes.txt`は`quotes.txt.dat`)
+fn dat_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".dat");
+    PathBuf::from(path)
+}
+
+/// 1作品分のエントリ (`kakidashi --format fortune`と同じ形式)
+fn entry(record: &WorkRecord) -> String {
+    format!(
+        "{}\n\t\t-- {}『{}』\n",
+        record.text, record.author, record.title
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_fortune() {
+        let record = |text: &str| WorkRecord {
+            id: "1".to_string(),
+            author: "著者".to_string(),
+            author_id: "1".to_string(),
+            title: "題".to_string(),
+            text: text.to_string(),
+            ..WorkRecord::default()
+        };
+        let output_path = std::env::temp_dir().join("kakidashi-test-fortune/quotes.txt");
+        export_fortune(&[record("あ。"), record("いう。")], &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(
+            content,
+            "あ。\n\t\t-- 著者『題』\n%\nいう。\n\t\t-- 著者『題』\n%\n"
+        );
+
+        let dat = std::fs::read(output_path.with_file_name("quotes.txt.dat")).unwrap();
+        let word = |i: usize| u32::from_be_bytes(dat[i * 4..i * 4 + 4].try_into().unwrap());
+        assert_eq!(word(0), STRFILE_VERSION);
+        assert_eq!(word(1), 2);
+        assert_eq!(word(2), 31);
+        assert_eq!(word(3), 28);
+        assert_eq!(&dat[20..24], b"%\0\0\0");
+        assert_eq!((word(6), word(7), word(8)), (0, 30, 63));
+    }
+
+    #[test]
+    fn test_dat_path() {
+        assert_eq!(
+            dat_path(Path::new("target/fortune/kakidashi")),
+            Path::new("target/fortune/kakidashi.dat")
+        );
+        assert_eq!(
+            dat_path(Path::new("quotes.txt")),
+            Path::new("quotes.txt.dat")
+        );
+    }
+}
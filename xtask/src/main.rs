@@ -1,22 +1,209 @@
 use anyhow::{Ok, Result};
-use flate2::{Compression, write::GzEncoder};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
-use std::fs::{File, create_dir_all};
-use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+mod dataset;
+mod diff;
+mod document;
 mod extractor;
+mod fortune;
+mod manifest;
 mod models;
+mod overrides;
+mod review;
+mod verify;
 
-use extractor::{extract_authors, extract_links, extract_text_from_zip, extract_works};
+use dataset::{compress_csv, read_records, read_rows, write_csv};
+use diff::DiffFormat;
+use extractor::{extract_authors, extract_links, extract_reading, extract_works, read_body};
+use fortune::export_fortune;
+use manifest::Manifest;
 use models::WorkRecord;
+use overrides::Override;
+use verify::{Baseline, bail_on_errors, validate_records};
 
 const INPUT_PATH: &str = "aozorabunko";
 const OUTPUT_CSV_PATH: &str = "src/resources/data.csv";
 const OUTPUT_GZIP_PATH: &str = "src/resources/data.csv.gz";
+const OUTPUT_MANIFEST_PATH: &str = "src/resources/manifest.json";
+const OUTPUT_FORTUNE_PATH: &str = "target/fortune/kakidashi";
+const BASELINE_PATH: &str = "xtask/baseline.json";
+const GOLDEN_PATH: &str = "xtask/golden.csv";
+const OVERRIDES_PATH: &str = "xtask/overrides.json";
+
+/// kakidashiのデータセットを生成・検査する
+#[derive(Parser)]
+#[command(bin_name = "cargo xtask")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 青空文庫のリポジトリから書き出しを抽出してデータセットを書き出す
+    Extract(ExtractArgs),
+    /// データセットを検証する (必須項目、URL、CSVの往復変換、書き出しのある作品数の減少、名作の書き出しの変化)
+    Verify(VerifyArgs),
+    /// データセットの統計 (書き出しを抽出できた割合など) を出力する
+    Stats {
+        /// データセット (CSVまたは圧縮CSV)
+        #[arg(default_value = OUTPUT_CSV_PATH)]
+        input: PathBuf,
+    },
+    /// データセットを他の形式で書き出す
+    #[command(alias = "export-fortune")]
+    Export {
+        /// 出力先
+        #[arg(default_value = OUTPUT_FORTUNE_PATH)]
+        output: PathBuf,
+        /// データセット (CSVまたは圧縮CSV)
+        #[arg(long, default_value = OUTPUT_GZIP_PATH)]
+        input: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Fortune)]
+        format: ExportFormat,
+    },
+    /// 無作為に選んだ書き出しを確認し、判断を修正ファイルに書き込む
+    Review(ReviewArgs),
+    /// 2つのデータセットの書き出しの差分 (追加、削除、変更) を出力する
+    Diff {
+        /// 変更前のデータセット (CSVまたは圧縮CSV)
+        old: PathBuf,
+        /// 変更後のデータセット (CSVまたは圧縮CSV)
+        new: PathBuf,
+        #[arg(long, value_enum, default_value_t = DiffFormat::SideBySide)]
+        format: DiffFormat,
+        /// `side-by-side`形式の出力幅
+        #[arg(long, default_value_t = 120)]
+        width: usize,
+    },
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    /// 青空文庫のリポジトリ
+    #[arg(long, default_value = INPUT_PATH)]
+    input: PathBuf,
+    /// 全作品のCSVの出力先 (書き出しが空の作品を含む)
+    #[arg(long, default_value = OUTPUT_CSV_PATH)]
+    csv: PathBuf,
+    /// 埋め込み用の圧縮CSVの出力先 (書き出しが空の作品を除く)
+    #[arg(long, default_value = OUTPUT_GZIP_PATH)]
+    output: PathBuf,
+    /// マニフェスト (抽出元のコミット、作品数、圧縮CSVのSHA-256など) の出力先
+    #[arg(long, default_value = OUTPUT_MANIFEST_PATH)]
+    manifest: PathBuf,
+    /// 書き出しの修正 (作品IDごとの置き換え、除外、行の指定)
+    #[arg(long, default_value = OVERRIDES_PATH)]
+    overrides: PathBuf,
+    /// 抽出する作者の人物ID (複数指定可、省略時は全作者)
+    ///
+    /// 既定の出力先を一部の作者で上書きしないよう、`--csv`、`--output`、`--manifest`の指定が必要
+    #[arg(long = "author", value_name = "ID", requires_all = ["csv", "output", "manifest"])]
+    authors: Vec<String>,
+    /// スレッド数 (省略時はCPU数)
+    #[arg(long, short = 'j')]
+    threads: Option<usize>,
+    /// 進捗と所要時間を表示する
+    #[arg(long)]
+    progress: bool,
+}
+
+#[derive(Args)]
+struct VerifyArgs {
+    /// データセット (CSVまたは圧縮CSV)
+    #[arg(default_value = OUTPUT_GZIP_PATH)]
+    input: PathBuf,
+    /// 基準 (書き出しのある作品数)
+    #[arg(long, default_value = BASELINE_PATH)]
+    baseline: PathBuf,
+    /// 書き出しのある作品数の減少を許容する割合 (%)
+    #[arg(long, default_value_t = 1.0)]
+    threshold: f64,
+    /// 書き出しが変わってはならない作品の一覧 (列: id, author_id, title, text)
+    #[arg(long, default_value = GOLDEN_PATH)]
+    golden: PathBuf,
+    /// 検証に成功した場合、基準をデータセットの作品数で更新する (基準がなければ作成する)
+    #[arg(long)]
+    update_baseline: bool,
+    /// 基準との比較を省略する (基準がない場合はエラー)
+    #[arg(long, conflicts_with = "update_baseline")]
+    skip_baseline: bool,
+}
+
+#[derive(Args)]
+struct ReviewArgs {
+    /// 青空文庫のリポジトリ
+    #[arg(long, default_value = INPUT_PATH)]
+    input: PathBuf,
+    /// データセット (CSVまたは圧縮CSV)
+    #[arg(long, default_value = OUTPUT_CSV_PATH)]
+    dataset: PathBuf,
+    /// 書き出しの修正の書き込み先
+    #[arg(long, default_value = OVERRIDES_PATH)]
+    overrides: PathBuf,
+    /// 確認する作品数
+    #[arg(long, short = 'n', default_value_t = 20)]
+    sample: usize,
+    /// 書き出しの前後に表示する行数
+    #[arg(long, default_value_t = 3)]
+    context: usize,
+    /// 作品を選ぶ乱数のシード
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// 書き出し形式
+#[derive(ValueEnum, Clone, Copy)]
+enum ExportFormat {
+    /// fortune(6)形式とstrfile(8)形式のインデックス (`.dat`)
+    Fortune,
+}
 
 fn main() -> Result<()> {
-    let mut records = extract(INPUT_PATH);
+    match Cli::parse().command {
+        Command::Extract(args) => generate(&args),
+        Command::Verify(args) => verify(&args),
+        Command::Review(args) => review::review(&args),
+        Command::Stats { input } => {
+            print_stats(&read_records(&input)?);
+            Ok(())
+        }
+        Command::Export {
+            output,
+            input,
+            format: ExportFormat::Fortune,
+        } => {
+            let records = read_records(&input)?;
+            export_fortune(&records, &output)?;
+            println!("Records: {} ({})", records.len(), output.display());
+            Ok(())
+        }
+        Command::Diff {
+            old,
+            new,
+            format,
+            width,
+        } => {
+            let (old, new) = (read_records(&old)?, read_records(&new)?);
+            diff::print(&diff::diff(&old, &new), format, width);
+            Ok(())
+        }
+    }
+}
+
+fn generate(args: &ExtractArgs) -> Result<()> {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    let started = Instant::now();
+    let mut records = extract(args)?;
     records.sort_by_key(|r| {
         (
             r.author.clone(),
@@ -27,76 +214,157 @@ fn main() -> Result<()> {
     });
     println!("Records: {}", records.len());
 
-    write_csv(&records, OUTPUT_CSV_PATH)?;
+    bail_on_errors(&validate_records(&records))?;
+    write_csv(&records, &args.csv)?;
+    let total = records.len();
 
     records.retain(|r| !r.text.is_empty());
     println!("Records: {} (without empty text)", records.len());
 
-    compress_csv(&records, OUTPUT_GZIP_PATH)?;
+    compress_csv(&records, &args.output)?;
+    Manifest::new(&args.input, &args.output, total, records.len())?.write(&args.manifest)?;
+
+    if args.progress {
+        println!("Elapsed: {:.1?}", started.elapsed());
+    }
 
     Ok(())
 }
 
-fn extract(aozorabunko: &str) -> Vec<WorkRecord> {
-    extract_authors(&PathBuf::from(aozorabunko).join("index_pages/person_all.html"))
+fn extract(args: &ExtractArgs) -> Result<Vec<WorkRecord>> {
+    let overrides = &overrides::read(&args.overrides)?;
+    let authors: Vec<_> = extract_authors(&args.input.join("index_pages/person_all.html"))
         .unwrap_or_default()
+        .into_iter()
+        .filter(|author| args.authors.is_empty() || args.authors.contains(&author.id))
+        .collect();
+
+    let progress = if args.progress {
+        ProgressBar::new(authors.len() as u64).with_style(
+            ProgressStyle::with_template(
+                "{elapsed_precise} [{bar:40}] {pos}/{len} authors (ETA {eta})",
+            )
+            .expect("Invalid progress template")
+            .progress_chars("=> "),
+        )
+    } else {
+        ProgressBar::hidden()
+    };
+
+    let records = authors
         .into_par_iter()
+        .progress_with(progress.clone())
         .flat_map(|author| {
+            let author_reading = extract_reading(Path::new(&author.page_path), "作家名読み");
             extract_works(&author)
                 .unwrap_or_default()
                 .into_par_iter()
                 .flat_map(move |work| {
+                    let fix = overrides.get(&work.id);
+                    if fix == Some(&Override::Exclude) {
+                        return None;
+                    }
                     let work_link = extract_links(Path::new(&work.page_path))?;
-                    let text =
-                        extract_text_from_zip(Path::new(&work_link.zip_path)).unwrap_or_default();
+                    let body = read_body(&work_link);
+                    let opening = match overrides::apply(body.as_ref(), fix) {
+                        Result::Ok(opening) => opening?,
+                        Err(e) => return Some(Err(e.context(format!("Override for {}", work.id)))),
+                    };
+                    let citation = body.map(|body| body.citation).unwrap_or_default();
+                    let source =
+                        (!opening.text.is_empty()).then(|| opening.source.as_str().to_string());
 
-                    Some(WorkRecord {
+                    Some(Ok(WorkRecord {
+                        id: work.id,
                         author: author.name.clone(),
+                        author_id: author.id.clone(),
                         title: work.title,
-                        text,
-                        url: work_link.url,
-                    })
+                        text: opening.text,
+                        url: work_link.url.map(String::from),
+                        ruby: opening.ruby,
+                        author_reading: author_reading.clone(),
+                        title_reading: extract_reading(Path::new(&work.page_path), "作品名読み"),
+                        card_url: Some(work_link.card_url.into()),
+                        source,
+                        base_text: citation.base_text,
+                        first_published: citation.first_published,
+                        inputter: citation.inputter,
+                        proofreader: citation.proofreader,
+                    }))
                 })
         })
-        .collect::<Vec<WorkRecord>>()
+        .collect::<Result<Vec<WorkRecord>>>();
+    progress.finish_and_clear();
+    records
 }
 
-fn write_csv(records: &Vec<WorkRecord>, output_path: &str) -> Result<()> {
-    if let Some(parent) = Path::new(output_path).parent() {
-        create_dir_all(parent)?;
-    }
-    let mut writer = csv::Writer::from_path(output_path)?;
-
-    for record in records {
-        writer.write_record([
-            &record.author,
-            &record.title,
-            &record.text,
-            &record.url.clone().unwrap_or_default(),
-        ])?;
+fn verify(args: &VerifyArgs) -> Result<()> {
+    let rows = read_rows(&args.input)?;
+    let records: Vec<WorkRecord> = rows.iter().map(dataset::from_row).collect();
+    let baseline = if args.skip_baseline {
+        None
+    } else {
+        Baseline::read(&args.baseline)?
+    };
+    if baseline.is_none() && !args.skip_baseline && !args.update_baseline {
+        anyhow::bail!(
+            "Baseline not found: {} (create it with --update-baseline, or skip the count check with --skip-baseline)",
+            args.baseline.display()
+        );
     }
 
-    writer.flush()?;
+    let mut errors = validate_records(&records);
+    errors.extend(verify::check_round_trip(&rows));
+    if let Some(baseline) = &baseline {
+        errors.extend(verify::check_baseline(&records, baseline, args.threshold));
+    }
+    errors.extend(verify::check_golden(
+        &records,
+        &verify::read_golden(&args.golden)?,
+    ));
+    bail_on_errors(&errors)?;
 
+    let records_with_text = records.iter().filter(|r| !r.text.is_empty()).count();
+    println!(
+        "Records: {} OK, {records_with_text} with text (baseline: {})",
+        records.len(),
+        baseline.map_or("none".to_string(), |b| b.records_with_text.to_string())
+    );
+    if args.update_baseline {
+        Baseline { records_with_text }.write(&args.baseline)?;
+        println!("Updated baseline ({})", args.baseline.display());
+    }
     Ok(())
 }
 
-fn compress_csv(records: &Vec<WorkRecord>, output_path: &str) -> Result<()> {
-    let output = File::create(output_path)?;
-    let mut encoder = GzEncoder::new(output, Compression::default());
-
-    for record in records {
-        let line = format!(
-            "{},{},{},{}\n",
-            record.author,
-            record.title,
-            record.text,
-            record.url.clone().unwrap_or_default()
-        );
-        encoder.write_all(line.as_bytes())?;
-    }
-
-    encoder.finish()?;
+/// 書き出しを抽出できた割合などを出力する
+fn print_stats(records: &[WorkRecord]) {
+    let count = |predicate: &dyn Fn(&WorkRecord) -> bool| {
+        let count = records.iter().filter(|record| predicate(record)).count();
+        format!(
+            "{count} ({:.1}%)",
+            count as f64 * 100.0 / records.len().max(1) as f64
+        )
+    };
+    let mut authors: Vec<&str> = records.iter().map(|r| r.author_id.as_str()).collect();
+    authors.sort_unstable();
+    authors.dedup();
 
-    Ok(())
+    println!("Records: {}", records.len());
+    println!("Authors: {}", authors.len());
+    println!("With text: {}", count(&|r| !r.text.is_empty()));
+    println!(
+        "  from txt: {}",
+        count(&|r| r.source.as_deref() == Some("txt"))
+    );
+    println!(
+        "  from xhtml: {}",
+        count(&|r| r.source.as_deref() == Some("xhtml"))
+    );
+    println!("With ruby: {}", count(&|r| r.ruby.is_some()));
+    println!("With url: {}", count(&|r| r.url.is_some()));
+    println!(
+        "With readings: {}",
+        count(&|r| r.author_reading.is_some() && r.title_reading.is_some())
+    );
 }
@@ -0,0 +1,89 @@
+use anyhow::{Ok, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// データセットのマニフェスト (`kakidashi --dataset-info`で出力)
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    /// 抽出元の青空文庫リポジトリのコミット (取得できない場合はnull)
+    pub aozorabunko_commit: Option<String>,
+    /// 抽出日時 (RFC 3339)
+    pub extracted_at: String,
+    /// 抽出した作品数 (書き出しが空の作品を含む)
+    pub records: usize,
+    /// 埋め込む作品数 (書き出しが空の作品を除く)
+    pub records_with_text: usize,
+    pub extractor_version: &'static str,
+    /// 埋め込み用の圧縮CSVのSHA-256
+    pub sha256: String,
+}
+
+impl Manifest {
+    pub fn new(
+        aozorabunko: &Path,
+        data_path: &Path,
+        records: usize,
+        records_with_text: usize,
+    ) -> Result<Self> {
+        Ok(Manifest {
+            aozorabunko_commit: commit(aozorabunko),
+            extracted_at: OffsetDateTime::now_utc()
+                .replace_nanosecond(0)?
+                .format(&Rfc3339)?,
+            records,
+            records_with_text,
+            extractor_version: env!("CARGO_PKG_VERSION"),
+            sha256: sha256(&fs::read(data_path)?),
+        })
+    }
+
+    pub fn write(&self, output_path: &Path) -> Result<()> {
+        fs::write(output_path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+/// リポジトリのHEADのコミットハッシュ
+///
+/// サブモジュールが未初期化の場合に親リポジトリのコミットを返さないよう、
+/// `.git`がない場合はNoneを返す
+fn commit(repository: &Path) -> Option<String> {
+    if !repository.join(".git").exists() {
+        return None;
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -1,25 +1,100 @@
+use serde::Serialize;
+use url::Url;
+
+use crate::document::Line;
+
 #[derive(Debug, Clone)]
 pub struct Author {
+    /// 人物ID
+    pub id: String,
     pub name: String,
     pub page_path: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct Work {
+    pub id: String,
     pub title: String,
     pub page_path: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct WorkLink {
-    pub zip_path: String,
-    pub url: Option<String>,
+    /// テキストzipファイルのパス
+    pub zip_path: Option<String>,
+    /// 作品ファイル (XHTML) のパス
+    pub html_path: Option<String>,
+    /// 作品ファイル (XHTML) のURL
+    pub url: Option<Url>,
+    /// 図書カードのURL
+    pub card_url: Url,
 }
 
+/// 作品の本文 (レイアウト注記を解釈した青空文庫形式の行)
 #[derive(Debug, Clone)]
+pub struct Body {
+    pub lines: Vec<Line>,
+    pub source: Source,
+    /// 後付けの書誌情報
+    pub citation: Citation,
+}
+
+/// 後付け (`底本：`以降) の書誌情報 (複数行の項目は空白でつなげる)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Citation {
+    /// 底本 (書名、出版社、発行年月日)
+    pub base_text: Option<String>,
+    /// 初出
+    pub first_published: Option<String>,
+    /// 入力者
+    pub inputter: Option<String>,
+    /// 校正者
+    pub proofreader: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Opening {
+    pub text: String,
+    /// `｜親文字《ルビ》`形式のルビ付きテキスト (ルビを含まない場合はNone)
+    pub ruby: Option<String>,
+    pub source: Source,
+}
+
+/// 書き出しの抽出元
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Source {
+    /// テキストzipファイル
+    #[default]
+    Text,
+    /// 作品ファイル (XHTML)
+    Xhtml,
+}
+
+impl Source {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Text => "txt",
+            Source::Xhtml => "xhtml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
 pub struct WorkRecord {
+    pub id: String,
     pub author: String,
+    pub author_id: String,
     pub title: String,
     pub text: String,
     pub url: Option<String>,
+    pub ruby: Option<String>,
+    pub author_reading: Option<String>,
+    pub title_reading: Option<String>,
+    pub card_url: Option<String>,
+    /// 書き出しの抽出元 (`txt`/`xhtml`、書き出しがない場合はNone)
+    pub source: Option<String>,
+    pub base_text: Option<String>,
+    pub first_published: Option<String>,
+    pub inputter: Option<String>,
+    pub proofreader: Option<String>,
 }
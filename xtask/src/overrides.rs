@@ -0,0 +1,120 @@
+use anyhow::{Ok, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::extractor::{extract_opening, extract_opening_at};
+use crate::models::{Body, Opening};
+
+/// 作品IDごとの書き出しの修正
+///
+/// ```json
+/// {
+///   "752": "accept",
+///   "1412": {"text": "修正した書き出し。"},
+///   "4232": "exclude",
+///   "43040": {"line": 12}
+/// }
+/// ```
+pub type Overrides = BTreeMap<String, Override>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Override {
+    /// 抽出結果を確認済み (`review`の対象から除く)
+    Accept,
+    /// 書き出しを置き換える
+    Text(String),
+    /// 作品を除外する
+    Exclude,
+    /// 本文の指定した行 (1始まり) から書き出しを抽出する
+    Line(usize),
+}
+
+/// 修正ファイルを読み込む (ファイルがない場合は空)
+pub fn read(path: &Path) -> Result<Overrides> {
+    if !path.exists() {
+        return Ok(Overrides::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn write(overrides: &Overrides, path: &Path) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(overrides)? + "\n")?;
+    Ok(())
+}
+
+/// 修正を適用した書き出し (除外する場合はNone)
+///
+/// 指定した行が本文にない場合はエラー (書き出しが空になり作品が埋め込まれなくなるため)
+pub fn apply(body: Option<&Body>, fix: Option<&Override>) -> Result<Option<Opening>> {
+    let source = body.map(|body| body.source).unwrap_or_default();
+    Ok(match fix {
+        Some(Override::Exclude) => None,
+        Some(Override::Text(text)) => Some(Opening {
+            text: text.clone(),
+            ruby: None,
+            source,
+        }),
+        Some(Override::Line(number)) => {
+            let lines = body.map_or(0, |body| body.lines.len());
+            match body.and_then(|body| extract_opening_at(body, *number)) {
+                Some(opening) => Some(opening),
+                None => bail!("Line {number} does not exist in the body ({lines} lines)"),
+            }
+        }
+        Some(Override::Accept) | None => Some(body.map(extract_opening).unwrap_or_default()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::parse_body;
+    use crate::models::{Citation, Source};
+
+    #[test]
+    fn test_apply() {
+        let body = Body {
+            lines: parse_body(&[
+                "松若（その息。",
+                "　｜親譲《おやゆず》りの無鉄砲で小供の時から損ばかりして居る。",
+            ]),
+            source: Source::Text,
+            citation: Citation::default(),
+        };
+        let text =
+            |fix: Option<&Override>| apply(Some(&body), fix).unwrap().map(|opening| opening.text);
+
+        assert_eq!(
+            text(None),
+            Some("親譲りの無鉄砲で小供の時から損ばかりして居る。".to_string())
+        );
+        assert_eq!(text(Some(&Override::Accept)), text(None));
+        assert_eq!(
+            text(Some(&Override::Line(1))),
+            Some("松若（その息。".to_string())
+        );
+        assert_eq!(
+            text(Some(&Override::Text("修正。".to_string()))),
+            Some("修正。".to_string())
+        );
+        assert_eq!(text(Some(&Override::Exclude)), None);
+
+        // 本文にない行はエラー
+        assert!(apply(Some(&body), Some(&Override::Line(0))).is_err());
+        assert!(apply(Some(&body), Some(&Override::Line(3))).is_err());
+        assert!(apply(None, Some(&Override::Line(1))).is_err());
+    }
+
+    #[test]
+    fn test_overrides_json() {
+        let overrides: Overrides = serde_json::from_str(
+            r#"{"1": "accept", "2": {"text": "あ。"}, "3": "exclude", "4": {"line": 12}}"#,
+        )
+        .unwrap();
+        assert_eq!(overrides["2"], Override::Text("あ。".to_string()));
+        assert_eq!(overrides["4"], Override::Line(12));
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{Ok, Result};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::io::{self, BufRead, Write};
+
+use crate::ReviewArgs;
+use crate::dataset::read_records;
+use crate::document::LineKind;
+use crate::extractor::{card_path, extract_links, opening_line, read_body};
+use crate::models::{Body, WorkRecord};
+use crate::overrides::{self, Override};
+
+/// 修正のない作品から無作為に選んだ書き出しを本文の前後の行と並べて表示し、
+/// 判断 (確認済み、修正、除外) を修正ファイルに書き込む
+///
+/// 判断ごとに書き込むため、途中で終了しても判断は失われない
+pub fn review(args: &ReviewArgs) -> Result<()> {
+    let records = read_records(&args.dataset)?;
+    let mut overrides = overrides::read(&args.overrides)?;
+
+    // 共著・翻訳の作品は複数の作者に載るため作品IDで重複を除く
+    let mut candidates: Vec<&WorkRecord> = records
+        .iter()
+        .filter(|record| !overrides.contains_key(&record.id))
+        .collect();
+    candidates.sort_by(|a, b| a.id.cmp(&b.id));
+    candidates.dedup_by(|a, b| a.id == b.id);
+    let mut rng = StdRng::seed_from_u64(args.seed.unwrap_or_else(rand::random));
+    candidates.shuffle(&mut rng);
+    candidates.truncate(args.sample);
+
+    let mut input = io::stdin().lock();
+    for (i, record) in candidates.iter().enumerate() {
+        println!(
+            "\n[{}/{}] {} {}『{}』 ({})",
+            i + 1,
+            candidates.len(),
+            record.id,
+            record.author,
+            record.title,
+            record.source.as_deref().unwrap_or("none")
+        );
+        let body = record
+            .card_url
+            .as_deref()
+            .and_then(|card_url| card_path(&args.input, card_url))
+            .and_then(|path| extract_links(&path))
+            .and_then(|work_link| read_body(&work_link));
+        match &body {
+            Some(body) => print_context(body, args.context),
+            None => println!("(source not found)"),
+        }
+        println!("opening: {}", record.text);
+
+        let lines = body.as_ref().map_or(0, |body| body.lines.len());
+        let Some(fix) = ask(&mut input, lines)? else {
+            break;
+        };
+        if let Some(fix) = fix {
+            overrides.insert(record.id.clone(), fix);
+            overrides::write(&overrides, &args.overrides)?;
+        }
+    }
+    println!(
+        "Overrides: {} ({})",
+        overrides.len(),
+        args.overrides.display()
+    );
+    Ok(())
+}
+
+/// 書き出しの行 (`>`) と前後`context`行を行番号付きで表示する (見出しと字下げされた行は種類も表示する)
+fn print_context(body: &Body, context: usize) {
+    let opening = opening_line(body);
+    let center = opening.unwrap_or(1);
+    let first = center.saturating_sub(context).max(1);
+    for number in first..=center + context {
+        let Some(line) = body.lines.get(number - 1) else {
+            break;
+        };
+        let marker = if Some(number) == opening { '>' } else { ' ' };
+        let kind = match line.kind {
+            LineKind::Heading => "  [heading]",
+            LineKind::Indented => "  [indented]",
+            LineKind::Text | LineKind::Blank => "",
+        };
+        println!("{marker} {number:>5} | {}{kind}", line.text);
+    }
+}
+
+/// 判断を尋ねる (終了する場合はNone、スキップする場合はSome(None))
+///
+/// 行の指定は本文の行数 (`lines`) の範囲に限る。空の入力で最初の問いに戻る
+fn ask(input: &mut impl BufRead, lines: usize) -> Result<Option<Option<Override>>> {
+    loop {
+        let Some(answer) = prompt(
+            input,
+            "[a]ccept, [l]ine, [t]ext, [e]xclude, [s]kip, [q]uit > ",
+        )?
+        else {
+            return Ok(None);
+        };
+        match answer.as_str() {
+            "a" => return Ok(Some(Some(Override::Accept))),
+            "e" => return Ok(Some(Some(Override::Exclude))),
+            "s" => return Ok(Some(None)),
+            "q" => return Ok(None),
+            "l" => {
+                let Some(number) = prompt(input, &format!("line number (1-{lines}) > "))? else {
+                    return Ok(None);
+                };
+                match number.parse() {
+                    Result::Ok(number @ 1..) if number <= lines => {
+                        return Ok(Some(Some(Override::Line(number))));
+                    }
+                    _ if number.is_empty() => {}
+                    _ => println!("No line {number}"),
+                }
+            }
+            "t" => {
+                let Some(text) = prompt(input, "replacement text > ")? else {
+                    return Ok(None);
+                };
+                if !text.is_empty() {
+                    return Ok(Some(Some(Override::Text(text))));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 1行読み込む (入力の終わりではNone)
+fn prompt(input: &mut impl BufRead, message: &str) -> Result<Option<String>> {
+    print!("{message}");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask() {
+        let answer = |input: &str| ask(&mut input.as_bytes(), 20).unwrap();
+        assert_eq!(answer("a\n"), Some(Some(Override::Accept)));
+        assert_eq!(answer("x\ne\n"), Some(Some(Override::Exclude)));
+        assert_eq!(answer("s\n"), Some(None));
+        assert_eq!(answer("l\n12\n"), Some(Some(Override::Line(12))));
+        // 本文にない行、行番号でない入力は問い直す
+        assert_eq!(
+            answer("l\n0\nl\n21\nl\nx\nl\n\nl\n20\n"),
+            Some(Some(Override::Line(20)))
+        );
+        // 数字だけの書き出しも置き換えられる
+        assert_eq!(
+            answer("t\n\nt\n1984\n"),
+            Some(Some(Override::Text("1984".to_string())))
+        );
+        assert_eq!(answer("q\n"), None);
+        assert_eq!(answer(""), None);
+    }
+}
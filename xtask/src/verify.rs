@@ -0,0 +1,207 @@
+use anyhow::{Ok, Result};
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::dataset::{COLUMNS, from_row, parse_rows, write_rows};
+use crate::extractor::validate_url;
+use crate::models::WorkRecord;
+
+/// 検証の基準 (コミットしておき、抽出結果と比較する)
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Baseline {
+    /// 書き出しのある作品数
+    pub records_with_text: usize,
+}
+
+impl Baseline {
+    /// 基準を読み込む (ファイルがない場合はNone)
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+/// 書き出しが変わってはならない作品 (名作の書き出し)
+#[derive(Debug, Deserialize)]
+pub struct Golden {
+    pub id: String,
+    pub author_id: String,
+    pub title: String,
+    pub text: String,
+}
+
+/// ヘッダ付きCSVの一覧を読み込む
+pub fn read_golden(path: &Path) -> Result<Vec<Golden>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    Ok(reader.deserialize().collect::<Result<Vec<Golden>, _>>()?)
+}
+
+/// 全レコードの必須項目 (作品ID、作者、人物ID、作品名) があり、
+/// `url`と`card_url`が青空文庫のURLであることを検証する
+pub fn validate_records(records: &[WorkRecord]) -> Vec<String> {
+    records
+        .iter()
+        .flat_map(|record| {
+            let missing = [
+                ("id", &record.id),
+                ("author", &record.author),
+                ("author_id", &record.author_id),
+                ("title", &record.title),
+            ]
+            .into_iter()
+            .filter(|(_, value)| value.is_empty())
+            .map(|(name, _)| format!("Missing {name}: {}", label(record)));
+            let invalid = [&record.url, &record.card_url]
+                .into_iter()
+                .flatten()
+                .filter_map(|url| validate_url(url).err());
+            missing.chain(invalid).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// 各行の列数と、書き出して読み直した結果が元の行と一致することを検証する
+pub fn check_round_trip(rows: &[StringRecord]) -> Vec<String> {
+    rows.iter()
+        .enumerate()
+        .filter_map(|(i, row)| {
+            let line = i + 1;
+            if row.len() != COLUMNS {
+                return Some(format!(
+                    "Line {line}: expected {COLUMNS} columns, found {}",
+                    row.len()
+                ));
+            }
+            let record = from_row(row);
+            let round_tripped = write_rows(std::slice::from_ref(&record), Vec::new())
+                .and_then(|bytes| parse_rows(bytes.as_slice()))
+                .map(|rows| rows.iter().map(from_row).collect::<Vec<_>>());
+            match round_tripped {
+                Result::Ok(records) if records == [record.clone()] => None,
+                Result::Ok(_) => Some(format!(
+                    "Line {line}: CSV round-trip changed {}",
+                    label(&record)
+                )),
+                Err(e) => Some(format!("Line {line}: CSV round-trip failed: {e}")),
+            }
+        })
+        .collect()
+}
+
+/// 書き出しのある作品数が基準から`threshold`% を超えて減っていないことを検証する
+pub fn check_baseline(records: &[WorkRecord], baseline: &Baseline, threshold: f64) -> Vec<String> {
+    let count = records.iter().filter(|r| !r.text.is_empty()).count();
+    let minimum = baseline.records_with_text as f64 * (1.0 - threshold / 100.0);
+    if (count as f64) < minimum {
+        vec![format!(
+            "Openings dropped from {} to {count} (more than {threshold}%)",
+            baseline.records_with_text
+        )]
+    } else {
+        Vec::new()
+    }
+}
+
+/// 一覧の作品の書き出しが変わっていないことを検証する
+pub fn check_golden(records: &[WorkRecord], golden: &[Golden]) -> Vec<String> {
+    golden
+        .iter()
+        .filter_map(|golden| {
+            let record = records
+                .iter()
+                .find(|r| r.id == golden.id && r.author_id == golden.author_id);
+            match record {
+                None => Some(format!(
+                    "Missing golden work: {}『{}』",
+                    golden.id, golden.title
+                )),
+                Some(record) if record.text != golden.text => Some(format!(
+                    "Golden opening changed: {}\n  expected: {}\n  actual:   {}",
+                    label(record),
+                    golden.text,
+                    record.text
+                )),
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// エラーがある場合はまとめて失敗させる
+pub fn bail_on_errors(errors: &[String]) -> Result<()> {
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "{} error(s) in dataset:\n{}",
+            errors.len(),
+            errors.join("\n")
+        );
+    }
+    Ok(())
+}
+
+fn label(record: &WorkRecord) -> String {
+    format!("{} {}『{}』", record.id, record.author, record.title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, text: &str) -> WorkRecord {
+        WorkRecord {
+            id: id.to_string(),
+            author: "夏目 漱石".to_string(),
+            author_id: "148".to_string(),
+            title: "題".to_string(),
+            text: text.to_string(),
+            ..WorkRecord::default()
+        }
+    }
+
+    #[test]
+    fn test_check_round_trip() {
+        // カンマ・引用符・改行を含む項目も引用符で囲んで書き出す
+        let records = [record("1", "「あ、\"い\"\nう」。")];
+        let bytes = write_rows(&records, Vec::new()).unwrap();
+        let rows = parse_rows(bytes.as_slice()).unwrap();
+        assert!(check_round_trip(&rows).is_empty());
+
+        // 引用符で囲まずに書き出した行は列数がずれる
+        let rows = parse_rows("1,夏目 漱石,148,題,あ,い。,,,,,,,,,,\n".as_bytes()).unwrap();
+        assert_eq!(
+            check_round_trip(&rows),
+            vec!["Line 1: expected 15 columns, found 16"]
+        );
+    }
+
+    #[test]
+    fn test_check_baseline_and_golden() {
+        let records = [record("789", "吾輩は猫である。"), record("1", "")];
+        let baseline = Baseline {
+            records_with_text: 2,
+        };
+        assert!(check_baseline(&records, &baseline, 50.0).is_empty());
+        assert_eq!(check_baseline(&records, &baseline, 10.0).len(), 1);
+
+        let golden = |text: &str| Golden {
+            id: "789".to_string(),
+            author_id: "148".to_string(),
+            title: "吾輩は猫である".to_string(),
+            text: text.to_string(),
+        };
+        assert!(check_golden(&records, &[golden("吾輩は猫である。")]).is_empty());
+        assert_eq!(
+            check_golden(&records, &[golden("吾輩は猫である。名前はまだ無い。")]).len(),
+            1
+        );
+    }
+}